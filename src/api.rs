@@ -0,0 +1,105 @@
+//! Requests and responses of the HTTP prover service, shared by the server and the remote
+//! prover client.
+
+use cairo_vm::air_private_input::AirPrivateInputSerializable;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Layout, ProverConfig, ProverParameters, PublicInput};
+
+/// Identifier of a job of the prover service.
+pub type JobId = u64;
+
+/// Identifier of an upload of the prover service.
+pub type UploadId = u64;
+
+/// Request to prove the execution of a compiled Cairo program.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProgramJobRequest {
+    /// Compiled program, as generated by `cairo-compile`.
+    pub program: serde_json::Value,
+    pub layout: Layout,
+    #[serde(default)]
+    pub allow_missing_builtins: Option<bool>,
+    #[serde(default)]
+    pub prover_config: Option<ProverConfig>,
+    /// Prover parameters. Generated for `verifier` if not specified.
+    #[serde(default)]
+    pub parameters: Option<ProverParameters>,
+    /// Verifier for which the parameters are generated, "stone" by default.
+    #[serde(default)]
+    pub verifier: Option<String>,
+}
+
+/// Request to prove a program execution from its artifacts.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArtifactsJobRequest {
+    pub public_input: PublicInput,
+    /// Private input. The memory and trace paths are ignored.
+    pub private_input: AirPrivateInputSerializable,
+    /// Content of the memory file, encoded as base64.
+    pub memory: String,
+    /// Content of the trace file, encoded as base64.
+    pub trace: String,
+    #[serde(default)]
+    pub prover_config: Option<ProverConfig>,
+    /// Prover parameters. Generated for `verifier` if not specified.
+    #[serde(default)]
+    pub parameters: Option<ProverParameters>,
+    /// Verifier for which the parameters are generated, "stone" by default.
+    #[serde(default)]
+    pub verifier: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct JobCreatedResponse {
+    pub job_id: JobId,
+}
+
+/// Status of a job, as returned by `GET /jobs/:job_id`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// The program is being run to generate the execution artifacts.
+    Executing,
+    /// The job is queued or being proven.
+    Proving,
+    Succeeded,
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct VerifyResponse {
+    pub valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Request to prove a program execution from artifacts uploaded with `POST /uploads`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadsJobRequest {
+    pub public_input: PublicInput,
+    /// Private input. The memory and trace paths are ignored.
+    pub private_input: AirPrivateInputSerializable,
+    /// Upload containing the memory file.
+    pub memory_upload_id: UploadId,
+    /// Upload containing the trace file.
+    pub trace_upload_id: UploadId,
+    pub prover_config: ProverConfig,
+    pub parameters: ProverParameters,
+}
+
+/// State of an upload, as returned by `GET /uploads/:upload_id` and after each chunk.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UploadStatus {
+    pub upload_id: UploadId,
+    /// Number of bytes received so far. The next chunk must start at this offset.
+    pub size: u64,
+}
+
+/// Body of the error responses.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorResponse {
+    pub error: String,
+}
@@ -0,0 +1,103 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use clap::Parser;
+
+use stone_prover_sdk::server::{serve, ServerConfig};
+
+/// HTTP service exposing the Stone prover and verifier.
+#[derive(Parser, Debug)]
+#[command(name = "stone-prover-server", version, about)]
+struct Cli {
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    address: SocketAddr,
+    /// Maximum number of prover processes running at the same time. Defaults to the number
+    /// of cores.
+    #[arg(long)]
+    max_concurrent_jobs: Option<usize>,
+    /// Maximum memory used by all running jobs, in bytes. Unlimited if not specified.
+    #[arg(long)]
+    memory_budget: Option<u64>,
+    /// Time during which the status and proof of a completed job are kept, in seconds.
+    #[arg(long)]
+    job_ttl: Option<u64>,
+    /// Time after which an upload that receives no chunk is abandoned, in seconds.
+    #[arg(long)]
+    upload_ttl: Option<u64>,
+}
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+
+    let mut config = ServerConfig::default();
+    if let Some(max_concurrent_jobs) = cli.max_concurrent_jobs {
+        config.pool.max_concurrent_jobs = max_concurrent_jobs;
+    }
+    if let Some(memory_budget) = cli.memory_budget {
+        config.pool.memory_budget = memory_budget;
+    }
+    if let Some(job_ttl) = cli.job_ttl {
+        config.job_ttl = Duration::from_secs(job_ttl);
+    }
+    if let Some(upload_ttl) = cli.upload_ttl {
+        config.upload_ttl = Duration::from_secs(upload_ttl);
+    }
+
+    println!("Listening on {}", cli.address);
+    serve(cli.address, config).await
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let cli = Cli::try_parse_from(["stone-prover-server"]).unwrap();
+        assert_eq!(cli.address, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(cli.max_concurrent_jobs, None);
+        assert_eq!(cli.memory_budget, None);
+        assert_eq!(cli.job_ttl, None);
+        assert_eq!(cli.upload_ttl, None);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let cli = Cli::try_parse_from([
+            "stone-prover-server",
+            "--address",
+            "0.0.0.0:3000",
+            "--max-concurrent-jobs",
+            "4",
+            "--memory-budget",
+            "8589934592",
+            "--job-ttl",
+            "60",
+            "--upload-ttl",
+            "30",
+        ])
+        .unwrap();
+        assert_eq!(cli.address, "0.0.0.0:3000".parse().unwrap());
+        assert_eq!(cli.max_concurrent_jobs, Some(4));
+        assert_eq!(cli.memory_budget, Some(8589934592));
+        assert_eq!(cli.job_ttl, Some(60));
+        assert_eq!(cli.upload_ttl, Some(30));
+    }
+
+    #[test]
+    fn test_parse_invalid_address() {
+        let error =
+            Cli::try_parse_from(["stone-prover-server", "--address", "localhost"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+    }
+}
@@ -0,0 +1,616 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use cairo_vm::air_private_input::AirPrivateInputSerializable;
+use cairo_vm::types::program::Program;
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+
+use stone_prover_sdk::cairo_vm::{
+    extract_execution_artifacts_to, make_bootloader_tasks, run_bootloader_in_proof_mode_to,
+    run_in_proof_mode, ArtifactSink, ExecutionArtifacts,
+};
+use stone_prover_sdk::fri::{
+    generate_prover_parameters, generate_prover_parameters_for_public_input,
+    generate_prover_parameters_with_security,
+    generate_prover_parameters_with_security_for_public_input, DEFAULT_LOG_N_COSETS,
+};
+use stone_prover_sdk::json::{read_json_from_file, write_json_to_file};
+use stone_prover_sdk::models::{
+    Artifact, Layout, Proof, ProverConfig, ProverParameters, ProverWorkingDirectory, PublicInput,
+    Verifier,
+};
+use stone_prover_sdk::proof_analysis::analyze_proof;
+use stone_prover_sdk::proof_encoding::{decode_proof, encode_proof, Compression};
+use stone_prover_sdk::prover::run_prover_with_artifacts;
+use stone_prover_sdk::prover_config::{
+    auto_tune_prover_config, ProverConfigPreset, SystemResources,
+};
+use stone_prover_sdk::verifier::{run_verifier, verify_proof_with_annotations};
+
+/// Command-line interface of the Stone prover SDK.
+#[derive(Parser, Debug)]
+#[command(name = "stone-sdk", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Execute a Cairo program in proof mode and write the prover inputs.
+    Run(RunArgs),
+    /// Generate prover parameters.
+    Params(ParamsArgs),
+    /// Prove a program execution from the files written by `run` or `bootload`.
+    Prove(ProveArgs),
+    /// Verify a proof.
+    Verify(VerifyArgs),
+    /// Execute programs and PIEs with the bootloader and write the prover inputs.
+    Bootload(BootloadArgs),
+    /// Print a summary of a proof.
+    Inspect(InspectArgs),
+    /// Convert a JSON proof to the compact binary encoding.
+    Pack(PackArgs),
+    /// Convert a compact proof back to JSON.
+    Unpack(UnpackArgs),
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    /// Compiled program.
+    #[arg(long)]
+    program: PathBuf,
+    #[arg(long, default_value = "starknet_with_keccak")]
+    layout: Layout,
+    #[arg(long)]
+    allow_missing_builtins: bool,
+    /// Directory where the prover inputs are written.
+    #[arg(long)]
+    output_dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct ParamsArgs {
+    /// Number of Cairo steps of the program. Assumes a layout with 16 trace rows per step,
+    /// prefer `public_input` for other layouts.
+    #[arg(
+        long,
+        required_unless_present = "public_input",
+        conflicts_with = "public_input"
+    )]
+    n_steps: Option<u32>,
+    /// Public input of the execution, as written by `run` or `bootload`. The parameters are
+    /// sized for its actual trace length.
+    #[arg(long)]
+    public_input: Option<PathBuf>,
+    #[arg(long, default_value = "stone")]
+    verifier: Verifier,
+    /// Target conjectured security of the proof, in bits. Defaults to 96 bits.
+    #[arg(long)]
+    security_bits: Option<u32>,
+    /// log₂ of the blowup factor used to reach `security_bits`.
+    #[arg(long, requires = "security_bits", default_value_t = DEFAULT_LOG_N_COSETS)]
+    log_n_cosets: u32,
+    /// Output file. The parameters are printed if not specified.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ProveArgs {
+    /// Directory containing the prover inputs, as written by `run` or `bootload`.
+    #[arg(long)]
+    input_dir: PathBuf,
+    /// Prover configuration file. The default configuration is used if not specified.
+    #[arg(long, conflicts_with_all = ["preset", "auto_tune"])]
+    prover_config: Option<PathBuf>,
+    /// Named prover configuration: low-memory, balanced or max-speed.
+    #[arg(long, conflicts_with = "auto_tune")]
+    preset: Option<ProverConfigPreset>,
+    /// Tune the prover configuration for the trace size and the resources of this machine.
+    #[arg(long)]
+    auto_tune: bool,
+    /// Prover parameters file. Generated for `verifier` if not specified.
+    #[arg(long)]
+    parameters: Option<PathBuf>,
+    #[arg(long, default_value = "stone")]
+    verifier: Verifier,
+    /// Target conjectured security of the proof, in bits. Ignored if `parameters` is specified.
+    #[arg(long)]
+    security_bits: Option<u32>,
+    /// log₂ of the blowup factor used to reach `security_bits`.
+    #[arg(long, requires = "security_bits", default_value_t = DEFAULT_LOG_N_COSETS)]
+    log_n_cosets: u32,
+    /// Output proof file.
+    #[arg(long)]
+    output: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    /// Proof file.
+    #[arg(long)]
+    proof: PathBuf,
+    /// Print the annotations generated by the verifier.
+    #[arg(long)]
+    annotations: bool,
+}
+
+#[derive(Args, Debug)]
+struct BootloadArgs {
+    /// Compiled bootloader program.
+    #[arg(long)]
+    bootloader: PathBuf,
+    /// Compiled programs to run.
+    #[arg(long = "program")]
+    programs: Vec<PathBuf>,
+    /// Cairo PIEs to run.
+    #[arg(long = "pie")]
+    pies: Vec<PathBuf>,
+    #[arg(long, default_value = "starknet_with_keccak")]
+    layout: Layout,
+    #[arg(long)]
+    allow_missing_builtins: bool,
+    /// File where the fact topologies of the tasks are written.
+    #[arg(long)]
+    fact_topologies: Option<PathBuf>,
+    /// Directory where the prover inputs are written.
+    #[arg(long)]
+    output_dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct InspectArgs {
+    /// Proof file.
+    #[arg(long)]
+    proof: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct PackArgs {
+    /// JSON proof file.
+    #[arg(long)]
+    proof: PathBuf,
+    /// Compress the proof with zstd at this level.
+    #[cfg(feature = "zstd")]
+    #[arg(long)]
+    zstd_level: Option<i32>,
+    /// Output compact proof file.
+    #[arg(long)]
+    output: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct UnpackArgs {
+    /// Compact proof file.
+    #[arg(long)]
+    proof: PathBuf,
+    /// Output JSON proof file.
+    #[arg(long)]
+    output: PathBuf,
+}
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+fn print_json<T: Serialize>(value: &T) -> CliResult {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn read_files(paths: &[PathBuf]) -> Result<Vec<Vec<u8>>, std::io::Error> {
+    paths.iter().map(std::fs::read).collect()
+}
+
+/// Creates the output directory and returns the destination of the memory and trace files.
+fn prepare_output_dir(output_dir: &Path) -> Result<ProverWorkingDirectory, std::io::Error> {
+    std::fs::create_dir_all(output_dir)?;
+    Ok(ProverWorkingDirectory::new(
+        output_dir.canonicalize()?,
+        false,
+    ))
+}
+
+fn artifact_sink(files: &ProverWorkingDirectory) -> ArtifactSink {
+    ArtifactSink::Files {
+        memory_file: files._memory_file.clone(),
+        trace_file: files._trace_file.clone(),
+    }
+}
+
+/// Writes the public and private inputs next to the memory and trace files.
+fn write_prover_inputs(
+    artifacts: &ExecutionArtifacts<Artifact>,
+    files: &ProverWorkingDirectory,
+) -> CliResult {
+    write_json_to_file(&artifacts.public_input, &files.public_input_file)?;
+    let private_input = artifacts.private_input.to_serializable(
+        files._trace_file.to_string_lossy().to_string(),
+        files._memory_file.to_string_lossy().to_string(),
+    );
+    write_json_to_file(private_input, &files.private_input_file)?;
+
+    println!(
+        "Executed {} steps with layout {}, prover inputs written to {}",
+        artifacts.public_input.n_steps,
+        artifacts.public_input.layout,
+        files.dir.display()
+    );
+    Ok(())
+}
+
+fn run(args: RunArgs) -> CliResult {
+    let program_content = std::fs::read(&args.program)?;
+    let files = prepare_output_dir(&args.output_dir)?;
+
+    let (cairo_runner, vm) = run_in_proof_mode(
+        &program_content,
+        args.layout,
+        Some(args.allow_missing_builtins),
+    )?;
+    let artifacts = extract_execution_artifacts_to(cairo_runner, vm, &artifact_sink(&files))?;
+    write_prover_inputs(&artifacts, &files)
+}
+
+fn prover_parameters(
+    n_steps: u32,
+    verifier: Verifier,
+    security_bits: Option<u32>,
+    log_n_cosets: u32,
+) -> Result<ProverParameters, Box<dyn Error>> {
+    Ok(match security_bits {
+        Some(security_bits) => generate_prover_parameters_with_security(
+            n_steps,
+            verifier,
+            security_bits,
+            log_n_cosets,
+        )?,
+        None => generate_prover_parameters(n_steps, verifier),
+    })
+}
+
+fn prover_parameters_for_public_input(
+    public_input: &PublicInput,
+    verifier: Verifier,
+    security_bits: Option<u32>,
+    log_n_cosets: u32,
+) -> Result<ProverParameters, Box<dyn Error>> {
+    Ok(match security_bits {
+        Some(security_bits) => generate_prover_parameters_with_security_for_public_input(
+            public_input,
+            verifier,
+            security_bits,
+            log_n_cosets,
+        )?,
+        None => generate_prover_parameters_for_public_input(public_input, verifier)?,
+    })
+}
+
+fn params(args: ParamsArgs) -> CliResult {
+    let parameters = match (&args.public_input, args.n_steps) {
+        (Some(path), _) => {
+            let public_input: PublicInput = read_json_from_file(path)?;
+            prover_parameters_for_public_input(
+                &public_input,
+                args.verifier,
+                args.security_bits,
+                args.log_n_cosets,
+            )?
+        }
+        (None, Some(n_steps)) => prover_parameters(
+            n_steps,
+            args.verifier,
+            args.security_bits,
+            args.log_n_cosets,
+        )?,
+        (None, None) => unreachable!("clap requires n_steps or public_input"),
+    };
+    match args.output {
+        Some(output) => Ok(write_json_to_file(parameters, output)?),
+        None => print_json(&parameters),
+    }
+}
+
+fn prove(args: ProveArgs) -> CliResult {
+    let files = ProverWorkingDirectory::new(args.input_dir, false);
+    let public_input: PublicInput = read_json_from_file(&files.public_input_file)?;
+    let private_input: AirPrivateInputSerializable =
+        read_json_from_file(&files.private_input_file)?;
+
+    let parameters: ProverParameters = match &args.parameters {
+        Some(path) => read_json_from_file(path)?,
+        None => prover_parameters_for_public_input(
+            &public_input,
+            args.verifier,
+            args.security_bits,
+            args.log_n_cosets,
+        )?,
+    };
+    let prover_config: ProverConfig = match (&args.prover_config, args.preset) {
+        (Some(path), _) => read_json_from_file(path)?,
+        (None, Some(preset)) => preset.prover_config(),
+        (None, None) if args.auto_tune => {
+            auto_tune_prover_config(&public_input, &parameters, &SystemResources::detect())
+        }
+        (None, None) => ProverConfig::default(),
+    };
+
+    let proof = run_prover_with_artifacts(
+        &public_input,
+        &private_input.into(),
+        &Artifact::OnDisk(files._memory_file.clone()),
+        &Artifact::OnDisk(files._trace_file.clone()),
+        &prover_config,
+        &parameters,
+    )?;
+    write_json_to_file(&proof, &args.output)?;
+    println!("Proof written to {}", args.output.display());
+    Ok(())
+}
+
+fn verify(args: VerifyArgs) -> CliResult {
+    if args.annotations {
+        let proof: Proof = read_json_from_file(&args.proof)?;
+        let annotations = verify_proof_with_annotations(&proof)?;
+        print_json(&annotations)?;
+    } else {
+        run_verifier(&args.proof)?;
+    }
+    println!("Proof is valid");
+    Ok(())
+}
+
+fn bootload(args: BootloadArgs) -> CliResult {
+    let bootloader = Program::from_bytes(&std::fs::read(&args.bootloader)?, Some("main"))?;
+    let tasks = make_bootloader_tasks(&read_files(&args.programs)?, &read_files(&args.pies)?)?;
+    let files = prepare_output_dir(&args.output_dir)?;
+
+    let artifacts = run_bootloader_in_proof_mode_to(
+        &bootloader,
+        tasks,
+        Some(args.layout),
+        Some(args.allow_missing_builtins),
+        args.fact_topologies,
+        &artifact_sink(&files),
+    )?;
+    write_prover_inputs(&artifacts, &files)
+}
+
+fn inspect(args: InspectArgs) -> CliResult {
+    let proof: Proof = read_json_from_file(&args.proof)?;
+    let analysis = analyze_proof(&proof);
+    let public_input = &proof.public_input;
+    let fri = &analysis.proof_parameters.stark.fri;
+
+    println!("Layout: {}", analysis.layout);
+    println!("Steps: {}", public_input.n_steps);
+    match analysis.trace_length {
+        Some(trace_length) => println!("Trace length: {trace_length}"),
+        None => println!("Trace length: too long"),
+    }
+    println!(
+        "Range check bounds: [{}, {}]",
+        public_input.rc_min, public_input.rc_max
+    );
+    println!(
+        "Public memory entries: {}",
+        public_input.public_memory.len()
+    );
+    let mut segments: Vec<_> = public_input.memory_segments.keys().collect();
+    segments.sort();
+    println!(
+        "Memory segments: {}",
+        segments
+            .iter()
+            .map(|segment| segment.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("Field: {}", analysis.proof_parameters.field);
+    println!("FRI steps: {:?}", fri.fri_step_list);
+    println!("FRI layers: {}", analysis.n_fri_layers);
+    println!(
+        "FRI last layer degree bound: {}",
+        fri.last_layer_degree_bound
+    );
+    println!("FRI queries: {}", fri.n_queries);
+    println!("Proof of work bits: {}", fri.proof_of_work_bits);
+    println!("Conjectured security: {} bits", analysis.security_bits);
+    println!("Proof size: {} bytes", analysis.proof_size);
+    println!(
+        "Verifiers: {}",
+        analysis
+            .verifiers
+            .iter()
+            .map(|verifier| verifier.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+fn pack(args: PackArgs) -> CliResult {
+    let proof: Proof = read_json_from_file(&args.proof)?;
+    #[cfg(feature = "zstd")]
+    let compression = match args.zstd_level {
+        Some(level) => Compression::Zstd(level),
+        None => Compression::None,
+    };
+    #[cfg(not(feature = "zstd"))]
+    let compression = Compression::None;
+
+    let encoded_proof = encode_proof(&proof, compression)?;
+    std::fs::write(&args.output, &encoded_proof)?;
+    println!(
+        "Compact proof written to {} ({} bytes)",
+        args.output.display(),
+        encoded_proof.len()
+    );
+    Ok(())
+}
+
+fn unpack(args: UnpackArgs) -> CliResult {
+    let encoded_proof = std::fs::read(&args.proof)?;
+    let proof = decode_proof(&encoded_proof)?;
+    write_json_to_file(&proof, &args.output)?;
+    println!("Proof written to {}", args.output.display());
+    Ok(())
+}
+
+fn main() -> CliResult {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Run(args) => run(args),
+        Command::Params(args) => params(args),
+        Command::Prove(args) => prove(args),
+        Command::Verify(args) => verify(args),
+        Command::Bootload(args) => bootload(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Pack(args) => pack(args),
+        Command::Unpack(args) => unpack(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, clap::Error> {
+        Cli::try_parse_from(std::iter::once("stone-sdk").chain(args.iter().copied()))
+            .map(|cli| cli.command)
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let Command::Params(args) = parse(&["params", "--n-steps", "1024"]).unwrap() else {
+            panic!("expected the params command");
+        };
+        assert_eq!(args.n_steps, Some(1024));
+        assert_eq!(args.public_input, None);
+        assert_eq!(args.verifier, Verifier::Stone);
+        assert_eq!(args.security_bits, None);
+        assert_eq!(args.log_n_cosets, DEFAULT_LOG_N_COSETS);
+        assert_eq!(args.output, None);
+
+        let Command::Params(args) = parse(&[
+            "params",
+            "--public-input",
+            "air_public_input.json",
+            "--verifier",
+            "cairo",
+            "--security-bits",
+            "80",
+            "--log-n-cosets",
+            "3",
+        ])
+        .unwrap() else {
+            panic!("expected the params command");
+        };
+        assert_eq!(args.n_steps, None);
+        assert_eq!(
+            args.public_input,
+            Some(PathBuf::from("air_public_input.json"))
+        );
+        assert_eq!(args.verifier, Verifier::Cairo);
+        assert_eq!(args.security_bits, Some(80));
+        assert_eq!(args.log_n_cosets, 3);
+    }
+
+    #[test]
+    fn test_parse_prove() {
+        let Command::Prove(args) = parse(&[
+            "prove",
+            "--input-dir",
+            "fibonacci",
+            "--preset",
+            "low-memory",
+            "--output",
+            "proof.json",
+        ])
+        .unwrap() else {
+            panic!("expected the prove command");
+        };
+        assert_eq!(args.input_dir, PathBuf::from("fibonacci"));
+        assert_eq!(args.prover_config, None);
+        assert_eq!(args.preset, Some(ProverConfigPreset::LowMemory));
+        assert!(!args.auto_tune);
+        assert_eq!(args.parameters, None);
+        assert_eq!(args.verifier, Verifier::Stone);
+        assert_eq!(args.output, PathBuf::from("proof.json"));
+    }
+
+    #[test]
+    fn test_parse_verify() {
+        let Command::Verify(args) = parse(&["verify", "--proof", "proof.json"]).unwrap() else {
+            panic!("expected the verify command");
+        };
+        assert_eq!(args.proof, PathBuf::from("proof.json"));
+        assert!(!args.annotations);
+
+        let Command::Verify(args) =
+            parse(&["verify", "--proof", "proof.json", "--annotations"]).unwrap()
+        else {
+            panic!("expected the verify command");
+        };
+        assert!(args.annotations);
+    }
+
+    #[rstest]
+    #[case::params_without_size(&["params"], ErrorKind::MissingRequiredArgument)]
+    #[case::params_with_two_sizes(
+        &["params", "--n-steps", "1024", "--public-input", "air_public_input.json"],
+        ErrorKind::ArgumentConflict
+    )]
+    #[case::params_cosets_without_security(
+        &["params", "--n-steps", "1024", "--log-n-cosets", "3"],
+        ErrorKind::MissingRequiredArgument
+    )]
+    #[case::params_unknown_verifier(
+        &["params", "--n-steps", "1024", "--verifier", "evm"],
+        ErrorKind::ValueValidation
+    )]
+    #[case::prove_config_and_preset(
+        &[
+            "prove",
+            "--input-dir",
+            "fibonacci",
+            "--prover-config",
+            "cpu_air_prover_config.json",
+            "--preset",
+            "balanced",
+            "--output",
+            "proof.json",
+        ],
+        ErrorKind::ArgumentConflict
+    )]
+    #[case::prove_preset_and_auto_tune(
+        &[
+            "prove",
+            "--input-dir",
+            "fibonacci",
+            "--preset",
+            "balanced",
+            "--auto-tune",
+            "--output",
+            "proof.json",
+        ],
+        ErrorKind::ArgumentConflict
+    )]
+    #[case::prove_without_output(
+        &["prove", "--input-dir", "fibonacci"],
+        ErrorKind::MissingRequiredArgument
+    )]
+    #[case::verify_without_proof(&["verify"], ErrorKind::MissingRequiredArgument)]
+    fn test_parse_invalid_arguments(#[case] args: &[&str], #[case] expected: ErrorKind) {
+        assert_eq!(parse(args).unwrap_err().kind(), expected);
+    }
+}
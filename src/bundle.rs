@@ -0,0 +1,299 @@
+use std::path::{Component, Path, PathBuf};
+
+use cairo_vm::air_private_input::{AirPrivateInput, AirPrivateInputSerializable};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::error::ProverError;
+use crate::json::{read_json_from_file, write_json_to_file};
+use crate::models::{
+    Artifact, Proof, ProverConfig, ProverParameters, ProverWorkingDirectory, PublicInput,
+};
+use crate::prover::run_prover_with_artifacts;
+
+/// Name of the manifest file at the root of a bundle.
+pub const BUNDLE_MANIFEST_FILE: &str = "manifest.json";
+
+/// Version of the bundle format written by `export_bundle`.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Describes the content of a proof job bundle.
+///
+/// All paths are relative to the bundle directory, so the bundle can be moved or archived
+/// freely.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundleManifest {
+    pub version: u32,
+    /// Version of the SDK that created the bundle.
+    pub sdk_version: String,
+    pub public_input_file: PathBuf,
+    pub private_input_file: PathBuf,
+    pub memory_file: PathBuf,
+    pub trace_file: PathBuf,
+    pub prover_config_file: PathBuf,
+    pub prover_parameter_file: PathBuf,
+    /// Standard error of the prover, if the job failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_log_file: Option<PathBuf>,
+}
+
+impl Default for BundleManifest {
+    fn default() -> Self {
+        Self {
+            version: BUNDLE_FORMAT_VERSION,
+            sdk_version: env!("CARGO_PKG_VERSION").to_string(),
+            public_input_file: PathBuf::from("public_input.json"),
+            private_input_file: PathBuf::from("private_input.json"),
+            memory_file: PathBuf::from("memory.bin"),
+            trace_file: PathBuf::from("trace.bin"),
+            prover_config_file: PathBuf::from("prover_config_file.json"),
+            prover_parameter_file: PathBuf::from("parameters.json"),
+            stderr_log_file: None,
+        }
+    }
+}
+
+/// A proof job loaded from a bundle, ready to be passed to the prover.
+#[derive(Debug)]
+pub struct ProofJobBundle {
+    pub manifest: BundleManifest,
+    pub public_input: PublicInput,
+    pub private_input: AirPrivateInput,
+    pub memory: Artifact,
+    pub trace: Artifact,
+    pub prover_config: ProverConfig,
+    pub parameters: ProverParameters,
+}
+
+impl ProofJobBundle {
+    /// Runs the prover on the bundled job.
+    pub fn replay(&self) -> Result<Proof, ProverError> {
+        run_prover_with_artifacts(
+            &self.public_input,
+            &self.private_input,
+            &self.memory,
+            &self.trace,
+            &self.prover_config,
+            &self.parameters,
+        )
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("failed to read or write the bundle")]
+    IoError(#[from] std::io::Error),
+    #[error("the format of a JSON file is invalid")]
+    SerdeError(#[from] serde_json::Error),
+    #[error("the private input does not specify the {0} file")]
+    MissingPrivateInputPath(&'static str),
+    #[error("unsupported bundle format version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("bundle paths must be relative to the bundle directory, got {0}")]
+    InvalidPath(PathBuf),
+}
+
+/// Resolves a path of the manifest against the bundle directory.
+///
+/// Absolute paths and `..` components are rejected, so a bundle cannot reference files
+/// outside of its directory.
+fn resolve_bundle_path(bundle_dir: &Path, path: &Path) -> Result<PathBuf, BundleError> {
+    let is_inside_bundle = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_inside_bundle {
+        return Err(BundleError::InvalidPath(path.to_path_buf()));
+    }
+    Ok(bundle_dir.join(path))
+}
+
+/// Reads the path of the memory or trace file from a serialized private input.
+fn private_input_path(
+    private_input: &serde_json::Value,
+    key: &'static str,
+) -> Result<PathBuf, BundleError> {
+    private_input
+        .get(key)
+        .and_then(|path| path.as_str())
+        .map(PathBuf::from)
+        .ok_or(BundleError::MissingPrivateInputPath(key))
+}
+
+/// Packages the files of a prover working directory into a self-contained bundle.
+///
+/// The memory and trace files are copied into the bundle, wherever they were located,
+/// and the private input is rewritten to point to them with relative paths.
+/// Returns the manifest of the bundle.
+///
+/// * `working_dir`: Prover working directory, ex: one kept after a failure with
+///                  `WorkingDirectoryPolicy::KeepOnFailure`.
+/// * `bundle_dir`: Directory where the bundle is created.
+pub fn export_bundle(
+    working_dir: &ProverWorkingDirectory,
+    bundle_dir: &Path,
+) -> Result<BundleManifest, BundleError> {
+    std::fs::create_dir_all(bundle_dir)?;
+    let mut manifest = BundleManifest::default();
+
+    let mut private_input: serde_json::Value =
+        read_json_from_file(&working_dir.private_input_file)?;
+    let memory_file = private_input_path(&private_input, "memory_path")?;
+    let trace_file = private_input_path(&private_input, "trace_path")?;
+    std::fs::copy(memory_file, bundle_dir.join(&manifest.memory_file))?;
+    std::fs::copy(trace_file, bundle_dir.join(&manifest.trace_file))?;
+
+    private_input["memory_path"] = manifest.memory_file.to_string_lossy().into();
+    private_input["trace_path"] = manifest.trace_file.to_string_lossy().into();
+    write_json_to_file(
+        &private_input,
+        bundle_dir.join(&manifest.private_input_file),
+    )?;
+
+    std::fs::copy(
+        &working_dir.public_input_file,
+        bundle_dir.join(&manifest.public_input_file),
+    )?;
+    std::fs::copy(
+        &working_dir.prover_config_file,
+        bundle_dir.join(&manifest.prover_config_file),
+    )?;
+    std::fs::copy(
+        &working_dir.prover_parameter_file,
+        bundle_dir.join(&manifest.prover_parameter_file),
+    )?;
+
+    if working_dir.stderr_log_file.exists() {
+        let stderr_log_file = PathBuf::from("prover_stderr.log");
+        std::fs::copy(
+            &working_dir.stderr_log_file,
+            bundle_dir.join(&stderr_log_file),
+        )?;
+        manifest.stderr_log_file = Some(stderr_log_file);
+    }
+
+    write_json_to_file(&manifest, bundle_dir.join(BUNDLE_MANIFEST_FILE))?;
+    Ok(manifest)
+}
+
+/// Loads a bundle created by `export_bundle`.
+///
+/// The memory and trace files are not loaded in memory, the returned artifacts point to
+/// the files listed in the manifest. The paths of the manifest are resolved against
+/// `bundle_dir`, so the bundle can be loaded from any working directory.
+///
+/// * `bundle_dir`: Directory containing the bundle.
+pub fn load_bundle(bundle_dir: &Path) -> Result<ProofJobBundle, BundleError> {
+    let manifest: BundleManifest = read_json_from_file(bundle_dir.join(BUNDLE_MANIFEST_FILE))?;
+    if manifest.version != BUNDLE_FORMAT_VERSION {
+        return Err(BundleError::UnsupportedVersion(manifest.version));
+    }
+
+    let resolve = |path: &PathBuf| resolve_bundle_path(bundle_dir, path);
+    let public_input_file = resolve(&manifest.public_input_file)?;
+    let private_input_file = resolve(&manifest.private_input_file)?;
+    let memory_file = resolve(&manifest.memory_file)?;
+    let trace_file = resolve(&manifest.trace_file)?;
+    let prover_config_file = resolve(&manifest.prover_config_file)?;
+    let prover_parameter_file = resolve(&manifest.prover_parameter_file)?;
+
+    // The memory and trace paths of the private input are ignored: the prover rewrites them.
+    let private_input: AirPrivateInputSerializable = read_json_from_file(private_input_file)?;
+
+    Ok(ProofJobBundle {
+        public_input: read_json_from_file(public_input_file)?,
+        private_input: private_input.into(),
+        memory: Artifact::OnDisk(memory_file),
+        trace: Artifact::OnDisk(trace_file),
+        prover_config: read_json_from_file(prover_config_file)?,
+        parameters: read_json_from_file(prover_parameter_file)?,
+        manifest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::prover::create_temp_dir;
+    use crate::test_utils::{prover_cli_test_case, ProverCliTestCase};
+
+    use super::*;
+
+    #[test]
+    fn test_load_bundle_unsupported_version() {
+        let bundle_dir = create_temp_dir().unwrap();
+        let manifest = BundleManifest {
+            version: BUNDLE_FORMAT_VERSION + 1,
+            ..Default::default()
+        };
+        write_json_to_file(&manifest, bundle_dir.join(BUNDLE_MANIFEST_FILE)).unwrap();
+
+        let result = load_bundle(&bundle_dir);
+        assert!(matches!(result, Err(BundleError::UnsupportedVersion(_))));
+        std::fs::remove_dir_all(bundle_dir).unwrap();
+    }
+
+    #[rstest]
+    #[case::absolute_path(PathBuf::from("/etc/passwd"))]
+    #[case::parent_dir(PathBuf::from("../memory.bin"))]
+    #[case::nested_parent_dir(PathBuf::from("data/../../memory.bin"))]
+    fn test_load_bundle_rejects_paths_outside_the_bundle(#[case] memory_file: PathBuf) {
+        let bundle_dir = create_temp_dir().unwrap();
+        let manifest = BundleManifest {
+            memory_file: memory_file.clone(),
+            ..Default::default()
+        };
+        write_json_to_file(&manifest, bundle_dir.join(BUNDLE_MANIFEST_FILE)).unwrap();
+
+        let result = load_bundle(&bundle_dir);
+        assert!(matches!(result, Err(BundleError::InvalidPath(path)) if path == memory_file));
+        std::fs::remove_dir_all(bundle_dir).unwrap();
+    }
+
+    fn make_working_dir(prover_cli_test_case: &ProverCliTestCase) -> ProverWorkingDirectory {
+        let mut working_dir = ProverWorkingDirectory::new(create_temp_dir().unwrap(), true);
+        working_dir.public_input_file = prover_cli_test_case.public_input_file.clone();
+        working_dir.private_input_file =
+            prover_cli_test_case.private_input_file.path().to_path_buf();
+        working_dir.prover_config_file = prover_cli_test_case.prover_config_file.clone();
+        working_dir.prover_parameter_file = prover_cli_test_case.prover_parameter_file.clone();
+        working_dir
+    }
+
+    /// Check that a bundle can be exported from a working directory and loaded back.
+    #[rstest]
+    fn test_export_and_load_bundle(prover_cli_test_case: ProverCliTestCase) {
+        let working_dir = make_working_dir(&prover_cli_test_case);
+        let bundle_dir = create_temp_dir().unwrap();
+
+        let manifest = export_bundle(&working_dir, &bundle_dir).unwrap();
+        let bundle = load_bundle(&bundle_dir).unwrap();
+
+        assert_eq!(bundle.manifest, manifest);
+        assert_eq!(
+            bundle.memory,
+            Artifact::OnDisk(bundle_dir.join(&manifest.memory_file))
+        );
+        assert_eq!(
+            bundle.trace,
+            Artifact::OnDisk(bundle_dir.join(&manifest.trace_file))
+        );
+        assert!(bundle_dir.join(&manifest.memory_file).exists());
+        assert!(bundle_dir.join(&manifest.trace_file).exists());
+        std::fs::remove_dir_all(bundle_dir).unwrap();
+    }
+
+    /// Check that a job exported to a bundle and loaded back generates the original proof.
+    #[rstest]
+    fn test_replay_bundle(prover_cli_test_case: ProverCliTestCase) {
+        let working_dir = make_working_dir(&prover_cli_test_case);
+        let bundle_dir = create_temp_dir().unwrap();
+
+        export_bundle(&working_dir, &bundle_dir).unwrap();
+        let bundle = load_bundle(&bundle_dir).unwrap();
+        let proof = bundle.replay().unwrap();
+
+        assert_eq!(proof.proof_hex, prover_cli_test_case.proof.proof_hex);
+        std::fs::remove_dir_all(bundle_dir).unwrap();
+    }
+}
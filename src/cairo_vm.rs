@@ -1,6 +1,8 @@
 use std::any::Any;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
 use cairo_vm::air_private_input::AirPrivateInput;
 use cairo_vm::air_public_input::PublicInputError;
@@ -16,6 +18,7 @@ use cairo_vm::types::errors::cairo_pie_error::CairoPieError;
 use cairo_vm::types::errors::program_errors::ProgramError;
 use cairo_vm::types::program::Program;
 use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::errors::runner_errors::RunnerError;
 use cairo_vm::vm::errors::trace_errors::TraceError;
 use cairo_vm::vm::errors::vm_exception::VmException;
 use cairo_vm::vm::runners::cairo_pie::CairoPie;
@@ -27,7 +30,7 @@ use thiserror::Error;
 
 use bincode::error::EncodeError;
 
-use crate::models::{Layout, PublicInput};
+use crate::models::{Artifact, BuiltinUsage, ExecutionResourcesReport, Layout, PublicInput};
 
 // Copied from cairo_run.rs and adapted to support injecting the bootloader input.
 // TODO: check if modifying CairoRunConfig to specify custom variables is accepted upstream.
@@ -138,11 +141,29 @@ pub fn make_bootloader_tasks(
     program_tasks.chain(cairo_pie_tasks).collect()
 }
 
-pub struct ExecutionArtifacts {
+/// Prover inputs generated by a program execution.
+///
+/// The memory and trace are held in memory by default. Functions writing them to an
+/// `ArtifactSink` return `ExecutionArtifacts<Artifact>`, which can reference on-disk files.
+pub struct ExecutionArtifacts<A = Vec<u8>> {
     pub public_input: PublicInput,
     pub private_input: AirPrivateInput,
-    pub memory: Vec<u8>,
-    pub trace: Vec<u8>,
+    pub memory: A,
+    pub trace: A,
+    pub resources: ExecutionResourcesReport,
+}
+
+/// Destination of the memory and trace artifacts of a program execution.
+#[derive(Debug, Clone, Default)]
+pub enum ArtifactSink {
+    /// Keep the artifacts in memory.
+    #[default]
+    InMemory,
+    /// Stream the artifacts to the specified files, without buffering them in memory.
+    Files {
+        memory_file: PathBuf,
+        trace_file: PathBuf,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -159,6 +180,10 @@ pub enum ExecutionError {
     EncodeTrace(EncodeTraceError),
     #[error(transparent)]
     SerializePublicInput(#[from] serde_json::Error),
+    #[error(transparent)]
+    GetExecutionResources(#[from] RunnerError),
+    #[error(transparent)]
+    WriteArtifact(#[from] std::io::Error),
 }
 
 /// An in-memory writer for bincode encoding.
@@ -180,6 +205,114 @@ impl bincode::enc::write::Writer for MemWriter {
     }
 }
 
+/// A writer for bincode encoding that forwards data to any `std::io::Write` implementation.
+pub struct IoWriter<W: Write> {
+    inner: W,
+    bytes_written: usize,
+}
+
+impl<W: Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            bytes_written: 0,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> bincode::enc::write::Writer for IoWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.inner
+            .write_all(bytes)
+            .map_err(|inner| EncodeError::Io {
+                inner,
+                index: self.bytes_written,
+            })?;
+        self.bytes_written += bytes.len();
+        Ok(())
+    }
+}
+
+/// Encodes the relocated memory of a program execution to `writer`, in the format expected by
+/// the prover.
+///
+/// * `cairo_runner` Cairo runner object, after relocation.
+/// * `writer`: Destination of the encoded memory.
+pub fn write_memory<W: Write>(cairo_runner: &CairoRunner, writer: W) -> Result<W, ExecutionError> {
+    let mut io_writer = IoWriter::new(writer);
+    write_encoded_memory(&cairo_runner.relocated_memory, &mut io_writer)
+        .map_err(ExecutionError::EncodeMemory)?;
+    Ok(io_writer.into_inner())
+}
+
+/// Encodes the relocated trace of a program execution to `writer`, in the format expected by
+/// the prover.
+///
+/// * `cairo_runner` Cairo runner object, after relocation.
+/// * `writer`: Destination of the encoded trace.
+pub fn write_trace<W: Write>(cairo_runner: &CairoRunner, writer: W) -> Result<W, ExecutionError> {
+    let trace = cairo_runner
+        .relocated_trace
+        .as_ref()
+        .ok_or(ExecutionError::GenerateTrace(TraceError::TraceNotEnabled))?;
+
+    let mut io_writer = IoWriter::new(writer);
+    write_encoded_trace(trace, &mut io_writer).map_err(ExecutionError::EncodeTrace)?;
+    Ok(io_writer.into_inner())
+}
+
+/// Streams an artifact to a file.
+fn write_artifact_file<F>(path: &Path, write_fn: F) -> Result<Artifact, ExecutionError>
+where
+    F: FnOnce(BufWriter<File>) -> Result<BufWriter<File>, ExecutionError>,
+{
+    let writer = BufWriter::new(File::create(path)?);
+    write_fn(writer)?.flush()?;
+    Ok(Artifact::OnDisk(path.to_path_buf()))
+}
+
+/// Computes the resources used by a program execution, relative to the capacity of the layout.
+///
+/// * `cairo_runner` Cairo runner object.
+/// * `vm`: Cairo VM object.
+/// * `layout`: Layout used to run the program.
+/// * `padded_n_steps`: Number of steps of the trace after padding.
+pub fn get_execution_resources_report(
+    cairo_runner: &CairoRunner,
+    vm: &VirtualMachine,
+    layout: Layout,
+    padded_n_steps: usize,
+) -> Result<ExecutionResourcesReport, RunnerError> {
+    let execution_resources = cairo_runner.get_execution_resources(vm)?;
+
+    let builtins = execution_resources
+        .builtin_instance_counter
+        .into_iter()
+        .map(|(builtin_name, used_instances)| {
+            let builtin_name = builtin_name.trim_end_matches("_builtin").to_string();
+            let usage = BuiltinUsage::new(
+                used_instances,
+                padded_n_steps,
+                layout.builtin_ratio(&builtin_name),
+            );
+            (builtin_name, usage)
+        })
+        .collect();
+
+    Ok(ExecutionResourcesReport {
+        layout,
+        n_steps: execution_resources.n_steps,
+        padded_n_steps,
+        n_memory_holes: execution_resources.n_memory_holes,
+        builtins,
+    })
+}
+
 /// Extracts execution artifacts from the runner and VM (after execution).
 ///
 /// * `cairo_runner` Cairo runner object.
@@ -188,30 +321,64 @@ pub fn extract_execution_artifacts(
     cairo_runner: CairoRunner,
     vm: VirtualMachine,
 ) -> Result<ExecutionArtifacts, ExecutionError> {
-    let memory = &cairo_runner.relocated_memory;
-    let trace = cairo_runner
-        .relocated_trace
-        .as_ref()
-        .ok_or(ExecutionError::GenerateTrace(TraceError::TraceNotEnabled))?;
-
-    let mut memory_writer = MemWriter::new();
-    write_encoded_memory(memory, &mut memory_writer).map_err(ExecutionError::EncodeMemory)?;
-    let memory_raw = memory_writer.buf;
+    let memory = write_memory(&cairo_runner, vec![])?;
+    let trace = write_trace(&cairo_runner, vec![])?;
+    extract_prover_inputs(&cairo_runner, &vm, memory, trace)
+}
 
-    let mut trace_writer = MemWriter::new();
-    write_encoded_trace(trace, &mut trace_writer).map_err(ExecutionError::EncodeTrace)?;
-    let trace_raw = trace_writer.buf;
+/// Extracts execution artifacts from the runner and VM (after execution), writing the memory
+/// and trace to the specified sink.
+///
+/// * `cairo_runner` Cairo runner object.
+/// * `vm`: Cairo VM object.
+/// * `sink`: Destination of the memory and trace artifacts.
+pub fn extract_execution_artifacts_to(
+    cairo_runner: CairoRunner,
+    vm: VirtualMachine,
+    sink: &ArtifactSink,
+) -> Result<ExecutionArtifacts<Artifact>, ExecutionError> {
+    let (memory, trace) = match sink {
+        ArtifactSink::InMemory => (
+            Artifact::InMemory(write_memory(&cairo_runner, vec![])?),
+            Artifact::InMemory(write_trace(&cairo_runner, vec![])?),
+        ),
+        ArtifactSink::Files {
+            memory_file,
+            trace_file,
+        } => (
+            write_artifact_file(memory_file, |writer| write_memory(&cairo_runner, writer))?,
+            write_artifact_file(trace_file, |writer| write_trace(&cairo_runner, writer))?,
+        ),
+    };
+    extract_prover_inputs(&cairo_runner, &vm, memory, trace)
+}
 
-    let cairo_vm_public_input = cairo_runner.get_air_public_input(&vm)?;
+/// Extracts the public and private inputs and the resources used by the execution, and bundles
+/// them with the memory and trace artifacts.
+fn extract_prover_inputs<A>(
+    cairo_runner: &CairoRunner,
+    vm: &VirtualMachine,
+    memory: A,
+    trace: A,
+) -> Result<ExecutionArtifacts<A>, ExecutionError> {
+    let cairo_vm_public_input = cairo_runner.get_air_public_input(vm)?;
     let public_input = PublicInput::try_from(cairo_vm_public_input)?;
 
-    let private_input = cairo_runner.get_air_private_input(&vm);
+    let private_input = cairo_runner.get_air_private_input(vm);
+
+    let resources = get_execution_resources_report(
+        cairo_runner,
+        vm,
+        public_input.layout.clone(),
+        public_input.n_steps as usize,
+    )?;
 
     Ok(ExecutionArtifacts {
         public_input,
         private_input,
-        memory: memory_raw,
-        trace: trace_raw,
+        memory,
+        trace,
+        resources,
     })
 }
 
@@ -222,6 +389,44 @@ pub fn run_bootloader_in_proof_mode(
     allow_missing_builtins: Option<bool>,
     fact_topologies_path: Option<PathBuf>,
 ) -> Result<ExecutionArtifacts, ExecutionError> {
+    let (cairo_runner, vm) = run_bootloader(
+        bootloader,
+        tasks,
+        layout,
+        allow_missing_builtins,
+        fact_topologies_path,
+    )?;
+    extract_execution_artifacts(cairo_runner, vm)
+}
+
+/// Runs the bootloader in proof mode and writes the memory and trace to the specified sink.
+///
+/// Use `ArtifactSink::Files` for large runs to avoid holding the memory and trace in memory.
+pub fn run_bootloader_in_proof_mode_to(
+    bootloader: &Program,
+    tasks: Vec<TaskSpec>,
+    layout: Option<Layout>,
+    allow_missing_builtins: Option<bool>,
+    fact_topologies_path: Option<PathBuf>,
+    sink: &ArtifactSink,
+) -> Result<ExecutionArtifacts<Artifact>, ExecutionError> {
+    let (cairo_runner, vm) = run_bootloader(
+        bootloader,
+        tasks,
+        layout,
+        allow_missing_builtins,
+        fact_topologies_path,
+    )?;
+    extract_execution_artifacts_to(cairo_runner, vm, sink)
+}
+
+fn run_bootloader(
+    bootloader: &Program,
+    tasks: Vec<TaskSpec>,
+    layout: Option<Layout>,
+    allow_missing_builtins: Option<bool>,
+    fact_topologies_path: Option<PathBuf>,
+) -> Result<(CairoRunner, VirtualMachine), ExecutionError> {
     let proof_mode = true;
     let layout = layout.unwrap_or(Layout::StarknetWithKeccak);
 
@@ -266,6 +471,56 @@ pub fn run_bootloader_in_proof_mode(
         &mut hint_processor,
         variables,
     )?;
+    Ok((cairo_runner, vm))
+}
 
-    extract_execution_artifacts(cairo_runner, vm)
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::test_utils::{parsed_prover_test_case, ParsedProverTestCase};
+
+    use super::*;
+
+    #[rstest]
+    fn test_file_sink_matches_in_memory_artifacts(parsed_prover_test_case: ParsedProverTestCase) {
+        let layout = parsed_prover_test_case.public_input.layout.clone();
+        let run = || {
+            run_in_proof_mode(
+                &parsed_prover_test_case.compiled_program,
+                layout.clone(),
+                None,
+            )
+            .unwrap()
+        };
+
+        let (cairo_runner, vm) = run();
+        let in_memory = extract_execution_artifacts(cairo_runner, vm).unwrap();
+
+        let output_dir = tempfile::tempdir().unwrap();
+        let memory_file = output_dir.path().join("memory.bin");
+        let trace_file = output_dir.path().join("trace.bin");
+        let sink = ArtifactSink::Files {
+            memory_file: memory_file.clone(),
+            trace_file: trace_file.clone(),
+        };
+        let (cairo_runner, vm) = run();
+        let on_disk = extract_execution_artifacts_to(cairo_runner, vm, &sink).unwrap();
+
+        assert!(matches!(&on_disk.memory, Artifact::OnDisk(path) if *path == memory_file));
+        assert!(matches!(&on_disk.trace, Artifact::OnDisk(path) if *path == trace_file));
+        assert!(!in_memory.memory.is_empty());
+        assert!(!in_memory.trace.is_empty());
+        assert_eq!(std::fs::read(&memory_file).unwrap(), in_memory.memory);
+        assert_eq!(std::fs::read(&trace_file).unwrap(), in_memory.trace);
+
+        assert_eq!(on_disk.public_input, in_memory.public_input);
+        assert_eq!(on_disk.resources, in_memory.resources);
+        assert_eq!(in_memory.resources.layout, layout);
+        assert_eq!(
+            in_memory.resources.padded_n_steps,
+            in_memory.public_input.n_steps as usize
+        );
+        assert!(in_memory.resources.n_steps <= in_memory.resources.padded_n_steps);
+    }
 }
@@ -0,0 +1,372 @@
+//! Run the Stone prover and verifier inside a container.
+//!
+//! `cpu_air_prover` and `cpu_air_verifier` are started with `docker run` (or a compatible
+//! runtime, ex: Podman) in an image that contains them, like the image built by
+//! `scripts/build-stone.sh`. The directories containing the prover files are bind-mounted
+//! at the same path in the container, so the paths written in the prover inputs stay valid.
+
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use cairo_vm::air_private_input::AirPrivateInput;
+
+use crate::error::{ProverError, VerifierError};
+use crate::models::{
+    Artifact, Proof, ProverConfig, ProverParameters, ProverWorkingDirectory, PublicInput,
+    WorkingDirectoryPolicy,
+};
+use crate::prover::{
+    prover_args_for_working_dir, run_prover_with_command, run_prover_with_command_async,
+};
+use crate::verifier::{run_verifier_command, run_verifier_command_async, verifier_args};
+
+const DEFAULT_RUNTIME: &str = "docker";
+
+/// Runs the prover and verifier in a container image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockerBackend {
+    /// Container runtime executable, ex: "docker" or "podman".
+    pub runtime: PathBuf,
+    /// Image containing `cpu_air_prover` and `cpu_air_verifier` in its `PATH`.
+    pub image: String,
+    /// Additional arguments passed to `run`, ex: `["--platform", "linux/amd64"]`.
+    pub extra_args: Vec<String>,
+}
+
+impl DockerBackend {
+    pub fn new(image: impl Into<String>) -> Self {
+        Self {
+            runtime: DEFAULT_RUNTIME.into(),
+            image: image.into(),
+            extra_args: vec![],
+        }
+    }
+
+    /// Builds the command running `program` in the container.
+    ///
+    /// Each directory of `mounts` is mounted at the same path in the container. On Unix,
+    /// the program runs as the owner of the first directory so that it can write its output
+    /// files there and the user can delete them afterwards.
+    fn command(
+        &self,
+        program: &str,
+        mounts: &[PathBuf],
+        args: Vec<OsString>,
+    ) -> std::process::Command {
+        let mut command = std::process::Command::new(&self.runtime);
+        command.args(["run", "--rm", "--entrypoint", program]);
+
+        #[cfg(unix)]
+        if let Some(owner) = mounts.first().and_then(|dir| directory_owner(dir)) {
+            command.arg("--user").arg(owner);
+        }
+
+        let mounts: BTreeSet<&PathBuf> = mounts.iter().collect();
+        for dir in mounts {
+            let mut volume = OsString::from(dir);
+            volume.push(":");
+            volume.push(dir);
+            command.arg("--volume").arg(volume);
+        }
+
+        command.args(&self.extra_args).arg(&self.image).args(args);
+        command
+    }
+
+    fn prover_command(&self, prover_working_dir: &ProverWorkingDirectory) -> std::process::Command {
+        let mut mounts = vec![prover_working_dir.dir.clone()];
+        // Artifacts on disk are used in place and may live outside of the working directory.
+        for artifact_file in [
+            &prover_working_dir._memory_file,
+            &prover_working_dir._trace_file,
+        ] {
+            if let Some(dir) = artifact_file.parent() {
+                mounts.push(dir.to_path_buf());
+            }
+        }
+        self.command(
+            "cpu_air_prover",
+            &mounts,
+            prover_args_for_working_dir(prover_working_dir),
+        )
+    }
+
+    fn verifier_command(&self, in_file: &Path) -> Result<std::process::Command, std::io::Error> {
+        let in_file = std::path::absolute(in_file)?;
+        let mounts: Vec<PathBuf> = in_file
+            .parent()
+            .map(Path::to_path_buf)
+            .into_iter()
+            .collect();
+        Ok(self.command(
+            "cpu_air_verifier",
+            &mounts,
+            verifier_args(&in_file, None, None),
+        ))
+    }
+
+    /// Run the Stone Prover in the container on the specified program execution.
+    ///
+    /// * `public_input`: the public prover input generated by the Cairo program.
+    /// * `private_input`: the private prover input generated by the Cairo program.
+    /// * `memory`: the memory output of the Cairo program.
+    /// * `trace`: the execution trace of the Cairo program.
+    /// * `prover_config`: prover configuration.
+    /// * `parameters`: prover parameters for the Cairo program.
+    pub fn run_prover(
+        &self,
+        public_input: &PublicInput,
+        private_input: &AirPrivateInput,
+        memory: &Artifact,
+        trace: &Artifact,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+    ) -> Result<Proof, ProverError> {
+        let (proof, _) = self.run_prover_with_policy(
+            public_input,
+            private_input,
+            memory,
+            trace,
+            prover_config,
+            parameters,
+            &WorkingDirectoryPolicy::TempAutoDelete,
+        )?;
+        Ok(proof)
+    }
+
+    /// Run the Stone Prover in the container, with control over the working directory.
+    ///
+    /// See `prover::run_prover_with_policy` for details.
+    ///
+    /// * `public_input`: the public prover input generated by the Cairo program.
+    /// * `private_input`: the private prover input generated by the Cairo program.
+    /// * `memory`: the memory output of the Cairo program.
+    /// * `trace`: the execution trace of the Cairo program.
+    /// * `prover_config`: prover configuration.
+    /// * `parameters`: prover parameters for the Cairo program.
+    /// * `policy`: where to write the prover files and when to delete them.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_prover_with_policy(
+        &self,
+        public_input: &PublicInput,
+        private_input: &AirPrivateInput,
+        memory: &Artifact,
+        trace: &Artifact,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+        policy: &WorkingDirectoryPolicy,
+    ) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+        run_prover_with_command(
+            public_input,
+            private_input,
+            (&absolute_artifact(memory)?).into(),
+            (&absolute_artifact(trace)?).into(),
+            prover_config,
+            parameters,
+            &absolute_policy(policy)?,
+            |prover_working_dir| self.prover_command(prover_working_dir),
+        )
+    }
+
+    /// Run the Stone Prover in the container, asynchronously.
+    ///
+    /// * `public_input`: the public prover input generated by the Cairo program.
+    /// * `private_input`: the private prover input generated by the Cairo program.
+    /// * `memory`: the memory output of the Cairo program.
+    /// * `trace`: the execution trace of the Cairo program.
+    /// * `prover_config`: prover configuration.
+    /// * `parameters`: prover parameters for the Cairo program.
+    pub async fn run_prover_async(
+        &self,
+        public_input: &PublicInput,
+        private_input: &AirPrivateInput,
+        memory: &Artifact,
+        trace: &Artifact,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+    ) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+        self.run_prover_with_policy_async(
+            public_input,
+            private_input,
+            memory,
+            trace,
+            prover_config,
+            parameters,
+            &WorkingDirectoryPolicy::TempAutoDelete,
+        )
+        .await
+    }
+
+    /// Run the Stone Prover in the container asynchronously, with control over the working
+    /// directory.
+    ///
+    /// * `public_input`: the public prover input generated by the Cairo program.
+    /// * `private_input`: the private prover input generated by the Cairo program.
+    /// * `memory`: the memory output of the Cairo program.
+    /// * `trace`: the execution trace of the Cairo program.
+    /// * `prover_config`: prover configuration.
+    /// * `parameters`: prover parameters for the Cairo program.
+    /// * `policy`: where to write the prover files and when to delete them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn run_prover_with_policy_async(
+        &self,
+        public_input: &PublicInput,
+        private_input: &AirPrivateInput,
+        memory: &Artifact,
+        trace: &Artifact,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+        policy: &WorkingDirectoryPolicy,
+    ) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+        run_prover_with_command_async(
+            public_input,
+            private_input,
+            (&absolute_artifact(memory)?).into(),
+            (&absolute_artifact(trace)?).into(),
+            prover_config,
+            parameters,
+            &absolute_policy(policy)?,
+            |prover_working_dir| self.prover_command(prover_working_dir),
+        )
+        .await
+    }
+
+    /// Run the Stone Verifier in the container.
+    ///
+    /// * `in_file`: Path to the proof generated from the prover. Corresponds to its "--out-file".
+    pub fn run_verifier(&self, in_file: &Path) -> Result<(), VerifierError> {
+        run_verifier_command(self.verifier_command(in_file)?)
+    }
+
+    /// Run the Stone Verifier in the container, asynchronously.
+    ///
+    /// * `in_file`: Path to the proof generated from the prover. Corresponds to its "--out-file".
+    pub async fn run_verifier_async(&self, in_file: &Path) -> Result<(), VerifierError> {
+        run_verifier_command_async(self.verifier_command(in_file)?).await
+    }
+}
+
+/// Relative paths would be resolved from the working directory of the container.
+fn absolute_artifact(artifact: &Artifact) -> Result<Artifact, std::io::Error> {
+    match artifact {
+        Artifact::OnDisk(path) => Ok(Artifact::OnDisk(std::path::absolute(path)?)),
+        artifact => Ok(artifact.clone()),
+    }
+}
+
+fn absolute_policy(
+    policy: &WorkingDirectoryPolicy,
+) -> Result<WorkingDirectoryPolicy, std::io::Error> {
+    match policy {
+        WorkingDirectoryPolicy::UserSupplied(dir) => Ok(WorkingDirectoryPolicy::UserSupplied(
+            std::path::absolute(dir)?,
+        )),
+        policy => Ok(policy.clone()),
+    }
+}
+
+#[cfg(unix)]
+fn directory_owner(dir: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(dir).ok()?;
+    Some(format!("{}:{}", metadata.uid(), metadata.gid()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    /// Writes a runtime shim that records its arguments and exits with `exit_code`.
+    fn write_runtime_shim(dir: &Path, exit_code: i32) -> (PathBuf, PathBuf) {
+        let shim = dir.join("runtime.sh");
+        let args_file = dir.join("args.txt");
+        std::fs::write(
+            &shim,
+            format!(
+                "#!/bin/sh\nfor arg in \"$@\"; do echo \"$arg\"; done > {}\nexit {}\n",
+                args_file.display(),
+                exit_code
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&shim, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (shim, args_file)
+    }
+
+    #[rstest]
+    fn test_prover_command() {
+        let working_dir = tempfile::tempdir().unwrap();
+        let mut prover_working_dir =
+            ProverWorkingDirectory::new(working_dir.path().to_path_buf(), false);
+        prover_working_dir._memory_file = PathBuf::from("/data/memory.bin");
+        prover_working_dir._trace_file = PathBuf::from("/data/trace.bin");
+
+        let mut backend = DockerBackend::new("stone-prover:latest");
+        backend.extra_args = vec!["--platform".into(), "linux/amd64".into()];
+        let command = backend.prover_command(&prover_working_dir);
+
+        assert_eq!(command.get_program(), "docker");
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_owned()).collect();
+        let working_dir_volume = format!(
+            "{}:{}",
+            working_dir.path().display(),
+            working_dir.path().display()
+        );
+        let position = |value: &str| args.iter().position(|arg| arg == value).unwrap();
+
+        assert_eq!(
+            &args[..4],
+            ["run", "--rm", "--entrypoint", "cpu_air_prover"]
+        );
+        assert_eq!(
+            args[position("--user") + 1],
+            directory_owner(working_dir.path()).unwrap().as_str()
+        );
+        assert!(args.contains(&working_dir_volume.into()));
+        assert!(args.contains(&"/data:/data".into()));
+        assert_eq!(args[position("--platform") + 1], "linux/amd64");
+        // The arguments of the prover follow the image.
+        let image_position = position("stone-prover:latest");
+        assert_eq!(
+            args[image_position + 1..],
+            prover_args_for_working_dir(&prover_working_dir)
+        );
+    }
+
+    #[rstest]
+    fn test_run_verifier_with_runtime_shim() {
+        let dir = tempfile::tempdir().unwrap();
+        let (shim, args_file) = write_runtime_shim(dir.path(), 0);
+        let proof_file = dir.path().join("proof.json");
+
+        let mut backend = DockerBackend::new("stone-prover:latest");
+        backend.runtime = shim;
+        backend.run_verifier(&proof_file).unwrap();
+
+        let args = std::fs::read_to_string(args_file).unwrap();
+        let volume = format!("{}:{}", dir.path().display(), dir.path().display());
+        assert!(args.lines().any(|arg| arg == volume));
+        assert!(args.ends_with(&format!(
+            "stone-prover:latest\ncpu_air_verifier\n--in_file\n{}\n",
+            proof_file.display()
+        )));
+    }
+
+    #[rstest]
+    fn test_run_verifier_with_failing_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let (shim, _) = write_runtime_shim(dir.path(), 1);
+
+        let mut backend = DockerBackend::new("stone-prover:latest");
+        backend.runtime = shim;
+        let result = backend.run_verifier(&dir.path().join("proof.json"));
+
+        assert!(matches!(result, Err(VerifierError::CommandError(_))));
+    }
+}
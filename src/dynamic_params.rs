@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::models::{ExecutionResourcesReport, Layout, PublicInput, CPU_COMPONENT_HEIGHT};
+
+/// Column layout parameters of the dynamic layout, required in addition to the component sizes.
+const COLUMN_PARAMS: [&str; 2] = ["num_columns_first", "num_columns_second"];
+
+/// Builtins of the dynamic layout, with the name of their row ratio parameter and the number
+/// of memory cells used by each builtin instance.
+const DYNAMIC_BUILTINS: [(&str, &str, u32); 10] = [
+    ("pedersen", "pedersen_builtin_row_ratio", 3),
+    ("range_check", "range_check_builtin_row_ratio", 1),
+    ("ecdsa", "ecdsa_builtin_row_ratio", 2),
+    ("bitwise", "bitwise_row_ratio", 5),
+    ("ec_op", "ec_op_builtin_row_ratio", 7),
+    ("keccak", "keccak_row_ratio", 16),
+    ("poseidon", "poseidon_row_ratio", 6),
+    ("range_check96", "range_check96_builtin_row_ratio", 1),
+    ("add_mod", "add_mod_row_ratio", 7),
+    ("mul_mod", "mul_mod_row_ratio", 7),
+];
+
+fn uses_builtin_param(builtin: &str) -> String {
+    format!("uses_{builtin}_builtin")
+}
+
+/// Returns the largest power of two lower or equal to `x`. `x` must be non-zero.
+fn prev_power_of_two(x: u32) -> u32 {
+    1 << x.ilog2()
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DynamicParamsError {
+    #[error("layout {0} does not accept dynamic parameters")]
+    UnexpectedDynamicParams(Layout),
+    #[error("the dynamic layout requires dynamic parameters")]
+    MissingDynamicParams,
+    #[error("missing dynamic parameter: {0}")]
+    MissingParameter(String),
+    #[error("dynamic parameter {name} must be 0 or 1, got {value}")]
+    InvalidFlag { name: String, value: u32 },
+    #[error("dynamic parameter {name} must be a power of two, got {value}")]
+    NotPowerOfTwo { name: String, value: u32 },
+    #[error("dynamic parameter {name} must be in [{min}, {max}], got {value}")]
+    OutOfRange {
+        name: String,
+        value: u32,
+        min: u32,
+        max: u32,
+    },
+    #[error(
+        "builtin {builtin} uses {used_instances} instances but only {capacity} fit in the trace"
+    )]
+    InsufficientCapacity {
+        builtin: String,
+        used_instances: u32,
+        capacity: u32,
+    },
+    #[error("the trace of {0} steps is too long")]
+    TraceTooLong(u64),
+}
+
+/// Computes the dynamic layout parameters that fit the resources used by a program.
+///
+/// Each used builtin gets the largest row ratio (i.e. the least trace cells) that still fits
+/// all its instances in the trace. A builtin can have at most one instance per Cairo step.
+///
+/// Only the component sizes are computed: the CPU component step, the memory, range check
+/// and diluted units row ratios and the usage flag and row ratio of each builtin. The column
+/// layout parameters of Stone (`num_columns_first`, `num_columns_second` and the `*_suboffset`
+/// parameters) depend on how the layout is compiled and must be added by the caller:
+/// `validate_dynamic_params` rejects parameters without them.
+///
+/// * `resources`: Resources used by the program. `resources.padded_n_steps` determines
+///   the trace length.
+pub fn compute_dynamic_params(
+    resources: &ExecutionResourcesReport,
+) -> Result<HashMap<String, u32>, DynamicParamsError> {
+    let layout_params = Layout::Dynamic.params();
+    let step_height = CPU_COMPONENT_HEIGHT * layout_params.cpu_component_step;
+    let too_long = || DynamicParamsError::TraceTooLong(resources.padded_n_steps as u64);
+    let n_steps = u32::try_from(resources.padded_n_steps).map_err(|_| too_long())?;
+    let trace_length = n_steps.checked_mul(step_height).ok_or_else(too_long)?;
+
+    let mut dynamic_params = HashMap::from([
+        (
+            "cpu_component_step".to_string(),
+            layout_params.cpu_component_step,
+        ),
+        (
+            "memory_units_row_ratio".to_string(),
+            step_height / layout_params.memory_units_per_step,
+        ),
+        (
+            "range_check_units_row_ratio".to_string(),
+            step_height / layout_params.rc_units,
+        ),
+        (
+            "diluted_units_row_ratio".to_string(),
+            layout_params
+                .diluted_pool
+                .as_ref()
+                .map(|diluted_pool| step_height / diluted_pool.units_per_step)
+                .unwrap_or(step_height),
+        ),
+    ]);
+
+    for (builtin, row_ratio_param, _) in DYNAMIC_BUILTINS {
+        let used_instances = resources
+            .builtins
+            .get(builtin)
+            .map(|usage| usage.used_instances as u32)
+            .unwrap_or(0);
+        if used_instances > n_steps {
+            return Err(DynamicParamsError::InsufficientCapacity {
+                builtin: builtin.to_string(),
+                used_instances,
+                capacity: n_steps,
+            });
+        }
+
+        // Unused builtins are given a single instance.
+        let row_ratio = match used_instances {
+            0 => trace_length,
+            used_instances => prev_power_of_two(trace_length / used_instances),
+        };
+
+        dynamic_params.insert(uses_builtin_param(builtin), (used_instances > 0) as u32);
+        dynamic_params.insert(row_ratio_param.to_string(), row_ratio);
+    }
+
+    Ok(dynamic_params)
+}
+
+fn get_param(dynamic_params: &HashMap<String, u32>, name: &str) -> Result<u32, DynamicParamsError> {
+    dynamic_params
+        .get(name)
+        .copied()
+        .ok_or_else(|| DynamicParamsError::MissingParameter(name.to_string()))
+}
+
+/// Checks that a row ratio is a power of two and that it fits in the trace.
+fn validate_row_ratio(name: &str, value: u32, trace_length: u32) -> Vec<DynamicParamsError> {
+    let mut errors = vec![];
+    if !value.is_power_of_two() {
+        errors.push(DynamicParamsError::NotPowerOfTwo {
+            name: name.to_string(),
+            value,
+        });
+    }
+    if value == 0 || value > trace_length {
+        errors.push(DynamicParamsError::OutOfRange {
+            name: name.to_string(),
+            value,
+            min: 1,
+            max: trace_length,
+        });
+    }
+    errors
+}
+
+/// Validates the dynamic parameters of a public input against the rules of its layout.
+///
+/// Layouts other than `dynamic` must not specify dynamic parameters. For the dynamic layout,
+/// all parameters must be present, row ratios must be powers of two that fit in the trace and
+/// each builtin must have enough capacity for the memory segment of the program. Of the column
+/// layout parameters, only the presence of the column counts is checked.
+///
+/// Returns all the violations found.
+pub fn validate_dynamic_params(public_input: &PublicInput) -> Result<(), Vec<DynamicParamsError>> {
+    let uses_dynamic_params = public_input.layout.params().uses_dynamic_params;
+    let dynamic_params = match (&public_input.dynamic_params, uses_dynamic_params) {
+        (None, false) => return Ok(()),
+        (Some(_), false) => {
+            return Err(vec![DynamicParamsError::UnexpectedDynamicParams(
+                public_input.layout.clone(),
+            )])
+        }
+        (None, true) => return Err(vec![DynamicParamsError::MissingDynamicParams]),
+        (Some(dynamic_params), true) => dynamic_params,
+    };
+
+    let Some(trace_length) = public_input.trace_length() else {
+        return Err(vec![DynamicParamsError::TraceTooLong(
+            public_input.n_steps as u64,
+        )]);
+    };
+    let mut errors = vec![];
+
+    for name in [
+        "memory_units_row_ratio",
+        "range_check_units_row_ratio",
+        "diluted_units_row_ratio",
+    ] {
+        match get_param(dynamic_params, name) {
+            Ok(value) => errors.extend(validate_row_ratio(name, value, trace_length)),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for name in COLUMN_PARAMS {
+        if let Err(e) = get_param(dynamic_params, name) {
+            errors.push(e);
+        }
+    }
+
+    match get_param(dynamic_params, "cpu_component_step") {
+        Ok(value) if !value.is_power_of_two() => errors.push(DynamicParamsError::NotPowerOfTwo {
+            name: "cpu_component_step".to_string(),
+            value,
+        }),
+        Ok(_) => {}
+        Err(e) => errors.push(e),
+    }
+
+    for (builtin, row_ratio_param, cells_per_instance) in DYNAMIC_BUILTINS {
+        let uses_builtin_param = uses_builtin_param(builtin);
+        let uses_builtin = match get_param(dynamic_params, &uses_builtin_param) {
+            Ok(value) if value > 1 => {
+                errors.push(DynamicParamsError::InvalidFlag {
+                    name: uses_builtin_param,
+                    value,
+                });
+                continue;
+            }
+            Ok(value) => value == 1,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        let row_ratio = match get_param(dynamic_params, row_ratio_param) {
+            Ok(row_ratio) => row_ratio,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let row_ratio_errors = validate_row_ratio(row_ratio_param, row_ratio, trace_length);
+        let row_ratio_is_valid = row_ratio_errors.is_empty();
+        errors.extend(row_ratio_errors);
+
+        let used_cells = public_input
+            .memory_segments
+            .get(builtin)
+            .map(|segment| segment.stop_ptr.saturating_sub(segment.begin_addr))
+            .unwrap_or(0);
+        let used_instances = used_cells.div_ceil(cells_per_instance);
+        let capacity = match (uses_builtin, row_ratio_is_valid) {
+            (true, true) => trace_length / row_ratio,
+            _ => 0,
+        };
+        if used_instances > capacity {
+            errors.push(DynamicParamsError::InsufficientCapacity {
+                builtin: builtin.to_string(),
+                used_instances,
+                capacity,
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{BuiltinUsage, MemorySegmentAddresses};
+
+    use super::*;
+
+    fn make_resources(
+        padded_n_steps: usize,
+        builtins: &[(&str, usize)],
+    ) -> ExecutionResourcesReport {
+        ExecutionResourcesReport {
+            layout: Layout::Dynamic,
+            n_steps: padded_n_steps,
+            padded_n_steps,
+            n_memory_holes: 0,
+            builtins: builtins
+                .iter()
+                .map(|(name, used_instances)| {
+                    (
+                        name.to_string(),
+                        BuiltinUsage::new(*used_instances, padded_n_steps, None),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn make_public_input(
+        layout: Layout,
+        n_steps: u32,
+        segments: &[(&str, u32)],
+        dynamic_params: Option<HashMap<String, u32>>,
+    ) -> PublicInput {
+        let memory_segments = segments
+            .iter()
+            .map(|(name, used_cells)| {
+                (
+                    name.to_string(),
+                    MemorySegmentAddresses {
+                        begin_addr: 1000,
+                        stop_ptr: 1000 + used_cells,
+                    },
+                )
+            })
+            .collect();
+
+        PublicInput {
+            layout,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps,
+            memory_segments,
+            public_memory: vec![],
+            dynamic_params,
+        }
+    }
+
+    #[test]
+    fn test_compute_dynamic_params() {
+        let resources = make_resources(1024, &[("pedersen", 100), ("range_check", 1024)]);
+        let dynamic_params = compute_dynamic_params(&resources).unwrap();
+
+        assert_eq!(dynamic_params["uses_pedersen_builtin"], 1);
+        // 16384 rows / 100 instances = 163 -> 128
+        assert_eq!(dynamic_params["pedersen_builtin_row_ratio"], 128);
+        assert_eq!(dynamic_params["uses_range_check_builtin"], 1);
+        assert_eq!(dynamic_params["range_check_builtin_row_ratio"], 16);
+        assert_eq!(dynamic_params["uses_bitwise_builtin"], 0);
+        assert_eq!(dynamic_params["bitwise_row_ratio"], 16384);
+    }
+
+    #[test]
+    fn test_compute_dynamic_params_insufficient_capacity() {
+        let resources = make_resources(1024, &[("pedersen", 2000)]);
+        assert_eq!(
+            compute_dynamic_params(&resources),
+            Err(DynamicParamsError::InsufficientCapacity {
+                builtin: "pedersen".to_string(),
+                used_instances: 2000,
+                capacity: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_compute_dynamic_params_trace_too_long() {
+        let resources = make_resources(1 << 30, &[]);
+        assert_eq!(
+            compute_dynamic_params(&resources),
+            Err(DynamicParamsError::TraceTooLong(1 << 30))
+        );
+    }
+
+    /// Adds the column layout parameters that `compute_dynamic_params` leaves to the caller.
+    fn with_column_params(mut dynamic_params: HashMap<String, u32>) -> HashMap<String, u32> {
+        dynamic_params.insert("num_columns_first".to_string(), 65);
+        dynamic_params.insert("num_columns_second".to_string(), 8);
+        dynamic_params
+    }
+
+    #[test]
+    fn test_validate_computed_dynamic_params() {
+        let resources = make_resources(1024, &[("pedersen", 100), ("range_check", 1024)]);
+        let dynamic_params = compute_dynamic_params(&resources).unwrap();
+        let public_input = make_public_input(
+            Layout::Dynamic,
+            1024,
+            &[("pedersen", 300), ("range_check", 1024)],
+            Some(with_column_params(dynamic_params)),
+        );
+
+        validate_dynamic_params(&public_input).unwrap();
+    }
+
+    #[test]
+    fn test_validate_dynamic_params_requires_column_params() {
+        let resources = make_resources(1024, &[("pedersen", 100)]);
+        let dynamic_params = compute_dynamic_params(&resources).unwrap();
+        let public_input = make_public_input(
+            Layout::Dynamic,
+            1024,
+            &[("pedersen", 300)],
+            Some(dynamic_params),
+        );
+
+        assert_eq!(
+            validate_dynamic_params(&public_input),
+            Err(vec![
+                DynamicParamsError::MissingParameter("num_columns_first".to_string()),
+                DynamicParamsError::MissingParameter("num_columns_second".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_dynamic_params_trace_too_long() {
+        let resources = make_resources(1024, &[]);
+        let mut dynamic_params = with_column_params(compute_dynamic_params(&resources).unwrap());
+        dynamic_params.insert("cpu_component_step".to_string(), 1 << 20);
+        let public_input = make_public_input(Layout::Dynamic, 1024, &[], Some(dynamic_params));
+
+        assert_eq!(
+            validate_dynamic_params(&public_input),
+            Err(vec![DynamicParamsError::TraceTooLong(1024)])
+        );
+    }
+
+    #[test]
+    fn test_validate_dynamic_params_layout_mismatch() {
+        let public_input = make_public_input(Layout::Small, 1024, &[], Some(HashMap::new()));
+        assert_eq!(
+            validate_dynamic_params(&public_input),
+            Err(vec![DynamicParamsError::UnexpectedDynamicParams(
+                Layout::Small
+            )])
+        );
+
+        let public_input = make_public_input(Layout::Dynamic, 1024, &[], None);
+        assert_eq!(
+            validate_dynamic_params(&public_input),
+            Err(vec![DynamicParamsError::MissingDynamicParams])
+        );
+    }
+
+    #[test]
+    fn test_validate_dynamic_params_violations() {
+        let resources = make_resources(1024, &[("pedersen", 100)]);
+        let mut dynamic_params = with_column_params(compute_dynamic_params(&resources).unwrap());
+        dynamic_params.insert("pedersen_builtin_row_ratio".to_string(), 1024);
+        dynamic_params.insert("uses_bitwise_builtin".to_string(), 2);
+        dynamic_params.insert("memory_units_row_ratio".to_string(), 3);
+        dynamic_params.remove("cpu_component_step");
+
+        let public_input = make_public_input(
+            Layout::Dynamic,
+            1024,
+            &[("pedersen", 300)],
+            Some(dynamic_params),
+        );
+        let errors = validate_dynamic_params(&public_input).unwrap_err();
+
+        assert!(errors.contains(&DynamicParamsError::NotPowerOfTwo {
+            name: "memory_units_row_ratio".to_string(),
+            value: 3
+        }));
+        assert!(errors.contains(&DynamicParamsError::MissingParameter(
+            "cpu_component_step".to_string()
+        )));
+        assert!(errors.contains(&DynamicParamsError::InvalidFlag {
+            name: "uses_bitwise_builtin".to_string(),
+            value: 2
+        }));
+        assert!(errors.contains(&DynamicParamsError::InsufficientCapacity {
+            builtin: "pedersen".to_string(),
+            used_instances: 100,
+            capacity: 16
+        }));
+        assert_eq!(errors.len(), 4);
+    }
+}
@@ -1,13 +1,27 @@
 use thiserror::Error;
 
+use crate::dynamic_params::DynamicParamsError;
+use crate::params_validation::ProverParametersError;
+
 #[derive(Error, Debug)]
 pub enum ProverError {
     #[error("prover could not be launched")]
     IoError(#[from] std::io::Error),
     #[error("prover run failed")]
     CommandError(std::process::Output),
+    #[error("prover run failed, the prover files were kept in {}", .working_dir.display())]
+    CommandErrorInWorkingDir {
+        output: std::process::Output,
+        working_dir: std::path::PathBuf,
+    },
     #[error("the format of a JSON file is invalid")]
     SerdeError(#[from] serde_json::Error),
+    #[error("the dynamic parameters of the public input are invalid")]
+    InvalidDynamicParams(Vec<DynamicParamsError>),
+    #[error("the prover parameters are invalid")]
+    InvalidParameters(Vec<ProverParametersError>),
+    #[error("prover job panicked or was cancelled: {0}")]
+    JobAborted(String),
 }
 
 #[derive(Error, Debug)]
@@ -1,11 +1,72 @@
-use crate::models::{FriParameters, ProverParameters, StarkParameters, Verifier};
+use crate::models::{
+    CommitmentHash, Field, FriParameters, HashFunction, PowHash, ProverParameters, PublicInput,
+    StarkParameters, StatementParameters, Verifier, CPU_COMPONENT_HEIGHT,
+};
 
-const DEFAULT_LAST_LAYER_DEGREE_BOUND: u32 = 64;
-const DEFAULT_N_QUERIES: u32 = 18;
-const DEFAULT_PROOF_OF_WORK_BITS: u32 = 24;
+pub(crate) const DEFAULT_LAST_LAYER_DEGREE_BOUND: u32 = 64;
+pub(crate) const DEFAULT_N_QUERIES: u32 = 18;
+pub(crate) const DEFAULT_PROOF_OF_WORK_BITS: u32 = 24;
+/// Default log₂ of the blowup factor.
+pub const DEFAULT_LOG_N_COSETS: u32 = 4;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum FriError {
+    #[error("log_n_cosets must be positive")]
+    InvalidLogNCosets,
+    #[error("the trace must have at least {} rows, got {0}", CPU_COMPONENT_HEIGHT)]
+    TraceTooShort(u32),
+    #[error("the trace of {0} steps is too long")]
+    TraceTooLong(u32),
+}
+
+/// Rules of a verifier on the FRI steps and layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FriStepConstraints {
+    /// Maximum value of a FRI step.
+    pub max_step: u32,
+    /// The first FRI step must be 0.
+    pub first_step_is_zero: bool,
+    /// The last FRI step may be 1.
+    pub last_step_can_be_one: bool,
+    /// Maximum number of FRI layers, i.e. of FRI steps including a leading 0.
+    pub max_layers: Option<usize>,
+    /// Maximum log₂ of the last layer degree bound.
+    pub max_last_layer_log_degree_bound: Option<u32>,
+}
+
+impl FriStepConstraints {
+    pub(crate) fn for_verifier(verifier: &Verifier) -> Self {
+        match verifier {
+            Verifier::Stone => Self {
+                max_step: 4,
+                first_step_is_zero: false,
+                last_step_can_be_one: true,
+                max_layers: None,
+                max_last_layer_log_degree_bound: None,
+            },
+            // The L1 verifier accepts FRI steps in [0, 1, 2].
+            Verifier::L1 => Self {
+                max_step: 2,
+                first_step_is_zero: true,
+                last_step_can_be_one: false,
+                max_layers: None,
+                max_last_layer_log_degree_bound: None,
+            },
+            // The Cairo verifier accepts FRI steps in [1, 4] after the first one, at most
+            // 15 layers and a last layer degree bound up to 2^15.
+            Verifier::Cairo => Self {
+                max_step: 4,
+                first_step_is_zero: true,
+                last_step_can_be_one: true,
+                max_layers: Some(15),
+                max_last_layer_log_degree_bound: Some(15),
+            },
+        }
+    }
+}
 
 /// Implements ceil(log2(x)).
-fn ceil_log2(x: u32) -> u32 {
+pub(crate) fn ceil_log2(x: u32) -> u32 {
     let mut log = x.ilog2();
     if !x.is_power_of_two() {
         log += 1;
@@ -45,6 +106,23 @@ fn compute_fri_steps(
 
 pub trait FriComputer {
     fn compute_fri_parameters(&self, nb_steps: u32) -> FriParameters;
+
+    /// Computes the FRI parameters for a trace of `trace_length` rows.
+    ///
+    /// `compute_fri_parameters` assumes that each step uses `CPU_COMPONENT_HEIGHT` rows,
+    /// which does not hold for all layouts. Use `PublicInput::trace_length` to get the actual
+    /// length of the trace.
+    ///
+    /// Returns an error if the trace is shorter than `CPU_COMPONENT_HEIGHT` rows.
+    fn compute_fri_parameters_for_trace_length(
+        &self,
+        trace_length: u32,
+    ) -> Result<FriParameters, FriError> {
+        if trace_length < CPU_COMPONENT_HEIGHT {
+            return Err(FriError::TraceTooShort(trace_length));
+        }
+        Ok(self.compute_fri_parameters(trace_length / CPU_COMPONENT_HEIGHT))
+    }
 }
 
 pub struct DefaultFriComputer;
@@ -55,7 +133,7 @@ impl FriComputer for DefaultFriComputer {
 
         let nb_steps_log = ceil_log2(nb_steps);
         let last_layer_degree_bound_log = ceil_log2(last_layer_degree_bound);
-        let max_step_value = 4;
+        let max_step_value = FriStepConstraints::for_verifier(&Verifier::Stone).max_step;
 
         let fri_steps =
             compute_fri_steps(nb_steps_log, last_layer_degree_bound_log, max_step_value);
@@ -73,8 +151,7 @@ pub struct L1VerifierFriComputer;
 
 impl FriComputer for L1VerifierFriComputer {
     fn compute_fri_parameters(&self, nb_steps: u32) -> FriParameters {
-        // The L1 verifier accepts FRI steps in [0, 1, 2].
-        let max_step_value = 2;
+        let max_step_value = FriStepConstraints::for_verifier(&Verifier::L1).max_step;
 
         let nb_steps_log = ceil_log2(nb_steps);
 
@@ -108,28 +185,252 @@ impl FriComputer for L1VerifierFriComputer {
     }
 }
 
-/// Generates prover parameters based on program parameters.
+/// Number of verifier-friendly commitment layers used by Cairo verifiers. Larger than any
+/// Merkle tree so that all the layers are verifier-friendly.
+const CAIRO_VERIFIER_FRIENDLY_COMMITMENT_LAYERS: u32 = 9999;
+
+pub struct CairoVerifierFriComputer;
+
+impl FriComputer for CairoVerifierFriComputer {
+    fn compute_fri_parameters(&self, nb_steps: u32) -> FriParameters {
+        let last_layer_degree_bound = DEFAULT_LAST_LAYER_DEGREE_BOUND;
+
+        let nb_steps_log = ceil_log2(nb_steps);
+        let last_layer_degree_bound_log = ceil_log2(last_layer_degree_bound);
+
+        // The Cairo verifier requires the first FRI step to be 0 and the other steps
+        // to be in [1, 4].
+        let mut fri_steps = vec![0];
+        fri_steps.extend(compute_fri_steps(
+            nb_steps_log,
+            last_layer_degree_bound_log,
+            FriStepConstraints::for_verifier(&Verifier::Cairo).max_step,
+        ));
+
+        FriParameters {
+            fri_step_list: fri_steps,
+            last_layer_degree_bound,
+            n_queries: DEFAULT_N_QUERIES,
+            proof_of_work_bits: DEFAULT_PROOF_OF_WORK_BITS,
+        }
+    }
+}
+
+/// Returns the `FriComputer` matching the constraints of `verifier`.
+fn fri_computer_for_verifier(verifier: &Verifier) -> &'static dyn FriComputer {
+    match verifier {
+        Verifier::Stone => &DefaultFriComputer,
+        Verifier::L1 => &L1VerifierFriComputer,
+        Verifier::Cairo => &CairoVerifierFriComputer,
+    }
+}
+
+/// Computes the conjectured security of a proof, in bits.
 ///
-/// * `nb_steps`: Number of Cairo steps of the program.
-/// * `last_layer_degree_bound`: Last layer degree bound.
-pub fn generate_prover_parameters(nb_steps: u32, verifier: Verifier) -> ProverParameters {
-    let fri_parameters = match verifier {
-        Verifier::L1 => L1VerifierFriComputer.compute_fri_parameters(nb_steps),
-        _ => DefaultFriComputer.compute_fri_parameters(nb_steps),
-    };
-    ProverParameters {
-        field: "PrimeField0".to_string(),
+/// Stone estimates the security of a proof as:
+/// # security = n_queries * log_n_cosets + proof_of_work_bits
+///
+/// * `fri_parameters`: FRI parameters of the proof.
+/// * `log_n_cosets`: log₂ of the blowup factor.
+pub fn conjectured_security_bits(fri_parameters: &FriParameters, log_n_cosets: u32) -> u32 {
+    fri_parameters.n_queries * log_n_cosets + fri_parameters.proof_of_work_bits
+}
+
+/// Computes FRI parameters reaching a target security level.
+///
+/// The FRI steps are computed as for `verifier`. The proof of work covers as many bits
+/// as allowed by `max_proof_of_work_bits`, and the number of queries covers the rest.
+pub struct SecurityLevelFriComputer {
+    verifier: Verifier,
+    security_bits: u32,
+    log_n_cosets: u32,
+    max_proof_of_work_bits: u32,
+}
+
+impl SecurityLevelFriComputer {
+    /// * `verifier`: verifier that will check the proof.
+    /// * `security_bits`: target conjectured security, in bits.
+    /// * `log_n_cosets`: log₂ of the blowup factor. Must be positive.
+    pub fn new(
+        verifier: Verifier,
+        security_bits: u32,
+        log_n_cosets: u32,
+    ) -> Result<Self, FriError> {
+        if log_n_cosets == 0 {
+            return Err(FriError::InvalidLogNCosets);
+        }
+        Ok(Self {
+            verifier,
+            security_bits,
+            log_n_cosets,
+            max_proof_of_work_bits: DEFAULT_PROOF_OF_WORK_BITS,
+        })
+    }
+
+    /// Sets the maximum number of proof of work bits. More bits make the proof smaller
+    /// but the prover slower.
+    pub fn with_max_proof_of_work_bits(mut self, max_proof_of_work_bits: u32) -> Self {
+        self.max_proof_of_work_bits = max_proof_of_work_bits;
+        self
+    }
+
+    /// Returns the conjectured security of the parameters computed for `nb_steps`, in bits.
+    /// Can exceed the target as the number of queries is rounded up.
+    pub fn achieved_security_bits(&self, nb_steps: u32) -> u32 {
+        conjectured_security_bits(&self.compute_fri_parameters(nb_steps), self.log_n_cosets)
+    }
+}
+
+impl FriComputer for SecurityLevelFriComputer {
+    fn compute_fri_parameters(&self, nb_steps: u32) -> FriParameters {
+        let mut fri_parameters =
+            fri_computer_for_verifier(&self.verifier).compute_fri_parameters(nb_steps);
+
+        let proof_of_work_bits = self.max_proof_of_work_bits.min(self.security_bits);
+        // At least one query is required to generate a proof.
+        let n_queries = (self.security_bits - proof_of_work_bits)
+            .div_ceil(self.log_n_cosets)
+            .max(1);
+
+        fri_parameters.n_queries = n_queries;
+        fri_parameters.proof_of_work_bits = proof_of_work_bits;
+        fri_parameters
+    }
+}
+
+/// Returns the trace length of `public_input`, or an error if it does not fit in 32 bits.
+fn trace_length(public_input: &PublicInput) -> Result<u32, FriError> {
+    public_input
+        .trace_length()
+        .ok_or(FriError::TraceTooLong(public_input.n_steps))
+}
+
+/// Builds prover parameters with the hash and commitment options expected by `verifier`.
+///
+/// The Stone and L1 verifiers use the defaults of Stone. Cairo verifiers require the channel
+/// and the commitments to use hash functions that are cheap to compute in Cairo.
+fn make_prover_parameters(
+    fri_parameters: FriParameters,
+    log_n_cosets: u32,
+    verifier: &Verifier,
+) -> ProverParameters {
+    let mut parameters = ProverParameters {
+        field: Field::PrimeField0,
         stark: StarkParameters {
             fri: fri_parameters,
-            log_n_cosets: 4,
+            log_n_cosets: log_n_cosets as i32,
         },
         use_extension_field: false,
+        channel_hash: None,
+        commitment_hash: None,
+        pow_hash: None,
+        n_verifier_friendly_commitment_layers: 0,
+        verifier_friendly_channel_updates: false,
+        verifier_friendly_commitment_hashes: false,
+        statement: None,
+    };
+
+    if *verifier == Verifier::Cairo {
+        parameters.channel_hash = Some(HashFunction::Poseidon3);
+        parameters.commitment_hash = Some(CommitmentHash::KECCAK256_MASKED160_LSB);
+        parameters.pow_hash = Some(PowHash::Keccak256);
+        // Same settings as the reference configuration of the Integrity verifier: the Merkle
+        // tree nodes are hashed with Poseidon, the table rows with the commitment hash.
+        parameters.n_verifier_friendly_commitment_layers =
+            CAIRO_VERIFIER_FRIENDLY_COMMITMENT_LAYERS;
+        parameters.verifier_friendly_channel_updates = true;
+        parameters.verifier_friendly_commitment_hashes = false;
+        parameters.statement = Some(StatementParameters {
+            page_hash: HashFunction::Pedersen,
+        });
     }
+
+    parameters
+}
+
+/// Generates prover parameters reaching a target security level.
+///
+/// * `nb_steps`: Number of Cairo steps of the program.
+/// * `verifier`: Verifier that will check the proof.
+/// * `security_bits`: Target conjectured security, in bits.
+/// * `log_n_cosets`: log₂ of the blowup factor, see `DEFAULT_LOG_N_COSETS`. A larger blowup
+///   factor requires fewer queries but makes the prover slower.
+pub fn generate_prover_parameters_with_security(
+    nb_steps: u32,
+    verifier: Verifier,
+    security_bits: u32,
+    log_n_cosets: u32,
+) -> Result<ProverParameters, FriError> {
+    let fri_computer =
+        SecurityLevelFriComputer::new(verifier.clone(), security_bits, log_n_cosets)?;
+    Ok(make_prover_parameters(
+        fri_computer.compute_fri_parameters(nb_steps),
+        log_n_cosets,
+        &verifier,
+    ))
+}
+
+/// Generates prover parameters reaching a target security level for a program execution.
+///
+/// The FRI parameters are computed from the actual trace length of the execution, see
+/// `generate_prover_parameters_for_public_input`.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `verifier`: Verifier that will check the proof.
+/// * `security_bits`: Target conjectured security, in bits.
+/// * `log_n_cosets`: log₂ of the blowup factor, see `DEFAULT_LOG_N_COSETS`.
+pub fn generate_prover_parameters_with_security_for_public_input(
+    public_input: &PublicInput,
+    verifier: Verifier,
+    security_bits: u32,
+    log_n_cosets: u32,
+) -> Result<ProverParameters, FriError> {
+    let fri_computer =
+        SecurityLevelFriComputer::new(verifier.clone(), security_bits, log_n_cosets)?;
+    let fri_parameters =
+        fri_computer.compute_fri_parameters_for_trace_length(trace_length(public_input)?)?;
+    Ok(make_prover_parameters(
+        fri_parameters,
+        log_n_cosets,
+        &verifier,
+    ))
+}
+
+/// Generates prover parameters based on program parameters.
+///
+/// * `nb_steps`: Number of Cairo steps of the program.
+/// * `last_layer_degree_bound`: Last layer degree bound.
+pub fn generate_prover_parameters(nb_steps: u32, verifier: Verifier) -> ProverParameters {
+    let fri_parameters = fri_computer_for_verifier(&verifier).compute_fri_parameters(nb_steps);
+    make_prover_parameters(fri_parameters, DEFAULT_LOG_N_COSETS, &verifier)
+}
+
+/// Generates prover parameters for a program execution.
+///
+/// Unlike `generate_prover_parameters`, the FRI parameters are computed from the actual trace
+/// length of the execution, which depends on its layout.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `verifier`: Verifier that will check the proof.
+pub fn generate_prover_parameters_for_public_input(
+    public_input: &PublicInput,
+    verifier: Verifier,
+) -> Result<ProverParameters, FriError> {
+    let fri_parameters = fri_computer_for_verifier(&verifier)
+        .compute_fri_parameters_for_trace_length(trace_length(public_input)?)?;
+    Ok(make_prover_parameters(
+        fri_parameters,
+        DEFAULT_LOG_N_COSETS,
+        &verifier,
+    ))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::*;
+    use crate::models::Layout;
     use rstest::rstest;
 
     #[rstest]
@@ -175,4 +476,191 @@ mod tests {
             expected_last_layer_degree_bound
         );
     }
+
+    #[rstest]
+    #[case(80, 14, 24)]
+    #[case(96, DEFAULT_N_QUERIES, DEFAULT_PROOF_OF_WORK_BITS)]
+    #[case(128, 26, 24)]
+    #[case(98, 19, 24)]
+    #[case(16, 1, 16)]
+    fn test_security_level_fri_computer(
+        #[case] security_bits: u32,
+        #[case] expected_n_queries: u32,
+        #[case] expected_proof_of_work_bits: u32,
+    ) {
+        let fri_computer =
+            SecurityLevelFriComputer::new(Verifier::Stone, security_bits, 4).unwrap();
+        let fri_parameters = fri_computer.compute_fri_parameters(32768);
+
+        assert_eq!(fri_parameters.n_queries, expected_n_queries);
+        assert_eq!(
+            fri_parameters.proof_of_work_bits,
+            expected_proof_of_work_bits
+        );
+        assert_eq!(
+            fri_parameters.fri_step_list,
+            DefaultFriComputer
+                .compute_fri_parameters(32768)
+                .fri_step_list
+        );
+        assert!(fri_computer.achieved_security_bits(32768) >= security_bits);
+    }
+
+    #[rstest]
+    fn test_security_level_fri_computer_max_proof_of_work_bits() {
+        let fri_computer = SecurityLevelFriComputer::new(Verifier::L1, 128, 2)
+            .unwrap()
+            .with_max_proof_of_work_bits(30);
+        let fri_parameters = fri_computer.compute_fri_parameters(768);
+
+        assert_eq!(fri_parameters.fri_step_list, vec![0, 2, 2, 2, 2]);
+        assert_eq!(fri_parameters.n_queries, 49);
+        assert_eq!(fri_parameters.proof_of_work_bits, 30);
+        assert_eq!(fri_computer.achieved_security_bits(768), 128);
+    }
+
+    #[rstest]
+    fn test_security_level_fri_computer_invalid_log_n_cosets() {
+        assert!(matches!(
+            SecurityLevelFriComputer::new(Verifier::Stone, 96, 0),
+            Err(FriError::InvalidLogNCosets)
+        ));
+    }
+
+    #[rstest]
+    #[case(2, 36)]
+    #[case(4, DEFAULT_N_QUERIES)]
+    #[case(6, 12)]
+    fn test_generate_prover_parameters_with_security(
+        #[case] log_n_cosets: u32,
+        #[case] expected_n_queries: u32,
+    ) {
+        let parameters =
+            generate_prover_parameters_with_security(32768, Verifier::Stone, 96, log_n_cosets)
+                .unwrap();
+
+        assert_eq!(parameters.stark.log_n_cosets, log_n_cosets as i32);
+        assert_eq!(parameters.stark.fri.n_queries, expected_n_queries);
+        assert_eq!(
+            conjectured_security_bits(&parameters.stark.fri, log_n_cosets),
+            96
+        );
+    }
+
+    #[rstest]
+    #[case(32768, vec ! [0, 4, 4, 4, 1])]
+    #[case(524288, vec ! [0, 4, 4, 4, 4, 1])]
+    #[case(768, vec ! [0, 4, 4])]
+    fn test_compute_fri_parameters_cairo_verifier(
+        #[case] nb_steps: u32,
+        #[case] expected_fri_steps: Vec<u32>,
+    ) {
+        let fri_parameters = CairoVerifierFriComputer.compute_fri_parameters(nb_steps);
+
+        assert_eq!(fri_parameters.fri_step_list, expected_fri_steps);
+        assert_eq!(
+            fri_parameters.last_layer_degree_bound,
+            DEFAULT_LAST_LAYER_DEGREE_BOUND
+        );
+    }
+
+    #[rstest]
+    #[case(Layout::Small, None, vec ! [4, 4, 4, 1])]
+    #[case(Layout::Dynamic, Some(1), vec ! [4, 4, 4, 1])]
+    #[case(Layout::Dynamic, Some(4), vec ! [4, 4, 4, 3])]
+    fn test_generate_prover_parameters_for_public_input(
+        #[case] layout: Layout,
+        #[case] cpu_component_step: Option<u32>,
+        #[case] expected_fri_steps: Vec<u32>,
+    ) {
+        let public_input = PublicInput {
+            layout,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: 32768,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: cpu_component_step
+                .map(|step| HashMap::from([("cpu_component_step".to_string(), step)])),
+        };
+        let parameters =
+            generate_prover_parameters_for_public_input(&public_input, Verifier::Stone).unwrap();
+
+        assert_eq!(parameters.stark.fri.fri_step_list, expected_fri_steps);
+    }
+
+    #[rstest]
+    fn test_generate_prover_parameters_with_security_for_public_input() {
+        let public_input = PublicInput {
+            layout: Layout::Dynamic,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: 32768,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: Some(HashMap::from([("cpu_component_step".to_string(), 4)])),
+        };
+        let parameters = generate_prover_parameters_with_security_for_public_input(
+            &public_input,
+            Verifier::Stone,
+            96,
+            4,
+        )
+        .unwrap();
+
+        // Same steps as `generate_prover_parameters_for_public_input`, sized for the trace.
+        assert_eq!(parameters.stark.fri.fri_step_list, vec![4, 4, 4, 3]);
+        assert_eq!(conjectured_security_bits(&parameters.stark.fri, 4), 96);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(CPU_COMPONENT_HEIGHT - 1)]
+    fn test_compute_fri_parameters_for_short_trace(#[case] trace_length: u32) {
+        assert_eq!(
+            DefaultFriComputer.compute_fri_parameters_for_trace_length(trace_length),
+            Err(FriError::TraceTooShort(trace_length))
+        );
+    }
+
+    #[rstest]
+    fn test_generate_prover_parameters_for_too_long_trace() {
+        let public_input = PublicInput {
+            layout: Layout::Dynamic,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: 1 << 28,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: Some(HashMap::from([("cpu_component_step".to_string(), 4)])),
+        };
+        assert_eq!(
+            generate_prover_parameters_for_public_input(&public_input, Verifier::Stone),
+            Err(FriError::TraceTooLong(1 << 28))
+        );
+    }
+
+    #[rstest]
+    fn test_generate_prover_parameters_for_cairo_verifier() {
+        let parameters = generate_prover_parameters(32768, Verifier::Cairo);
+        assert!(parameters.verifier_friendly_channel_updates);
+        assert!(!parameters.verifier_friendly_commitment_hashes);
+        assert_eq!(
+            parameters.n_verifier_friendly_commitment_layers,
+            CAIRO_VERIFIER_FRIENDLY_COMMITMENT_LAYERS
+        );
+        assert_eq!(
+            parameters.commitment_hash,
+            Some(CommitmentHash::KECCAK256_MASKED160_LSB)
+        );
+        assert_eq!(parameters.channel_hash, Some(HashFunction::Poseidon3));
+
+        // Stone defaults are used for the other verifiers and are not serialized.
+        let parameters = generate_prover_parameters(32768, Verifier::Stone);
+        let parameters_json = serde_json::to_value(&parameters).unwrap();
+        assert!(parameters_json
+            .get("verifier_friendly_channel_updates")
+            .is_none());
+        assert!(parameters_json.get("commitment_hash").is_none());
+    }
 }
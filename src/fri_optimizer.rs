@@ -0,0 +1,441 @@
+//! Search of FRI parameters trading off proof size, verifier cost and prover time.
+//!
+//! `DefaultFriComputer` and `L1VerifierFriComputer` fill the FRI steps greedily with a fixed
+//! last layer degree bound. `FriOptimizer` instead enumerates the valid combinations of FRI steps
+//! and last layer degree bounds and ranks them with cost estimates.
+//!
+//! The estimates are coarse models of the FRI protocol meant to compare parameters with each
+//! other, they do not predict the exact size of a proof or the running time of the prover.
+
+use crate::fri::{
+    ceil_log2, FriStepConstraints, DEFAULT_LAST_LAYER_DEGREE_BOUND, DEFAULT_LOG_N_COSETS,
+    DEFAULT_N_QUERIES, DEFAULT_PROOF_OF_WORK_BITS,
+};
+use crate::models::{FriParameters, Verifier};
+
+const FIELD_ELEMENT_BYTES: u64 = 32;
+const HASH_BYTES: u64 = 32;
+/// Cost of a hash relative to a field operation.
+const HASH_COST: u64 = 4;
+
+/// Criterion used to rank FRI parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FriObjective {
+    /// Minimize the size of the proof.
+    ProofSize,
+    /// Minimize the work of the verifier, ex: the gas cost of the L1 verifier.
+    VerifierCost,
+    /// Minimize the work of the prover.
+    ProverTime,
+}
+
+/// Estimated costs of a set of FRI parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FriCostEstimate {
+    /// Size of the FRI part of the proof, in bytes.
+    pub proof_size_bytes: u64,
+    /// Work of the verifier, in field operations.
+    pub verifier_cost: u64,
+    /// Work of the prover, in field operations.
+    pub prover_cost: u64,
+}
+
+impl FriCostEstimate {
+    fn cost(&self, objective: FriObjective) -> u64 {
+        match objective {
+            FriObjective::ProofSize => self.proof_size_bytes,
+            FriObjective::VerifierCost => self.verifier_cost,
+            FriObjective::ProverTime => self.prover_cost,
+        }
+    }
+}
+
+/// Valid FRI parameters with their estimated costs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FriCandidate {
+    pub fri_parameters: FriParameters,
+    pub estimate: FriCostEstimate,
+}
+
+/// Enumerates and ranks FRI parameters for a verifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FriOptimizer {
+    pub verifier: Verifier,
+    /// Maximum value of a FRI step.
+    pub max_step_value: u32,
+    /// Maximum last layer degree bound. Candidates use powers of two up to this value.
+    pub max_last_layer_degree_bound: u32,
+    pub n_queries: u32,
+    pub proof_of_work_bits: u32,
+    /// log₂ of the blowup factor.
+    pub log_n_cosets: u32,
+}
+
+impl FriOptimizer {
+    /// Creates an optimizer with the constraints of `verifier` and the default security
+    /// parameters.
+    pub fn new(verifier: Verifier) -> Self {
+        Self {
+            max_step_value: FriStepConstraints::for_verifier(&verifier).max_step,
+            verifier,
+            max_last_layer_degree_bound: DEFAULT_LAST_LAYER_DEGREE_BOUND,
+            n_queries: DEFAULT_N_QUERIES,
+            proof_of_work_bits: DEFAULT_PROOF_OF_WORK_BITS,
+            log_n_cosets: DEFAULT_LOG_N_COSETS,
+        }
+    }
+
+    /// Returns all the valid FRI parameters for a trace of `trace_length` rows.
+    ///
+    /// The trace length depends on the layout, see `PublicInput::trace_length`.
+    /// The FRI steps must satisfy:
+    /// # log₂(trace_length) = log₂(last_layer_degree_bound) + ∑fri_step_list
+    ///
+    /// Candidates also respect the limits of the verifier on the number of FRI layers and on
+    /// the last layer degree bound, ex: 15 layers and 2^15 for the Cairo verifier.
+    ///
+    /// Only one order of the steps is listed for each multiset of steps: the decreasing order,
+    /// adjusted for the constraints of the verifier (a leading 0 for the L1 and Cairo verifiers,
+    /// a last step different from 1 for the L1 verifier). In `estimate`, the cost of a layer
+    /// only grows with the size of its domain, and folding by the largest steps first shrinks
+    /// the domain of every following layer the most. Other orders are therefore never cheaper,
+    /// for any objective.
+    ///
+    /// Returns an empty list if `trace_length` is 0.
+    pub fn candidates(&self, trace_length: u32) -> Vec<FriCandidate> {
+        if trace_length == 0 {
+            return vec![];
+        }
+        let constraints = FriStepConstraints::for_verifier(&self.verifier);
+        let degree_bound_log = ceil_log2(trace_length);
+        let mut max_lldb_log = ceil_log2(self.max_last_layer_degree_bound).min(degree_bound_log);
+        if let Some(verifier_max_lldb_log) = constraints.max_last_layer_log_degree_bound {
+            max_lldb_log = max_lldb_log.min(verifier_max_lldb_log);
+        }
+
+        let mut candidates = vec![];
+        for lldb_log in 0..=max_lldb_log {
+            let mut partitions = vec![];
+            decreasing_partitions(
+                degree_bound_log - lldb_log,
+                self.max_step_value,
+                &mut vec![],
+                &mut partitions,
+            );
+
+            for steps in partitions {
+                let Some(fri_step_list) = self.apply_verifier_constraints(steps) else {
+                    continue;
+                };
+                if constraints
+                    .max_layers
+                    .is_some_and(|max_layers| fri_step_list.len() > max_layers)
+                {
+                    continue;
+                }
+                let fri_parameters = FriParameters {
+                    fri_step_list,
+                    last_layer_degree_bound: 1 << lldb_log,
+                    n_queries: self.n_queries,
+                    proof_of_work_bits: self.proof_of_work_bits,
+                };
+                candidates.push(FriCandidate {
+                    estimate: self.estimate(trace_length, &fri_parameters),
+                    fri_parameters,
+                });
+            }
+        }
+        candidates
+    }
+
+    /// Returns the valid FRI parameters, from the best to the worst for `objective`.
+    ///
+    /// Ties are broken with the other objectives.
+    pub fn ranked(&self, trace_length: u32, objective: FriObjective) -> Vec<FriCandidate> {
+        let mut candidates = self.candidates(trace_length);
+        candidates.sort_by_key(|candidate| {
+            let estimate = &candidate.estimate;
+            (
+                estimate.cost(objective),
+                estimate.proof_size_bytes,
+                estimate.verifier_cost,
+                estimate.prover_cost,
+                candidate.fri_parameters.fri_step_list.clone(),
+            )
+        });
+        candidates
+    }
+
+    /// Returns the best FRI parameters for `objective`.
+    pub fn best(&self, trace_length: u32, objective: FriObjective) -> Option<FriParameters> {
+        self.ranked(trace_length, objective)
+            .into_iter()
+            .next()
+            .map(|candidate| candidate.fri_parameters)
+    }
+
+    /// Adapts steps listed in decreasing order to the constraints of the verifier.
+    fn apply_verifier_constraints(&self, mut steps: Vec<u32>) -> Option<Vec<u32>> {
+        let constraints = FriStepConstraints::for_verifier(&self.verifier);
+        if !constraints.last_step_can_be_one && steps.last() == Some(&1) {
+            // Move the smallest step larger than 1 to the end, the other steps stay in
+            // decreasing order.
+            let index = steps.iter().rposition(|&step| step > 1)?;
+            let step = steps.remove(index);
+            steps.push(step);
+        }
+        if constraints.first_step_is_zero {
+            steps.insert(0, 0);
+        }
+        Some(steps)
+    }
+
+    /// Estimates the costs of FRI parameters.
+    ///
+    /// Each FRI layer folds the evaluation domain by 2^step. For every query, the verifier
+    /// receives the 2^step - 1 other elements of the folded coset with their Merkle path,
+    /// and the prover commits to every layer. The last layer is sent as coefficients.
+    ///
+    /// * `trace_length`: Number of rows of the trace, must be positive.
+    /// * `fri_parameters`: FRI parameters to evaluate.
+    pub fn estimate(&self, trace_length: u32, fri_parameters: &FriParameters) -> FriCostEstimate {
+        let n_queries = self.n_queries as u64;
+        let mut domain_log = ceil_log2(trace_length) + self.log_n_cosets;
+
+        let mut proof_size_bytes = 0;
+        let mut verifier_cost = 0;
+        let mut prover_cost = 0;
+        for &step in &fri_parameters.fri_step_list {
+            if step == 0 {
+                continue;
+            }
+            let coset_size = 1u64 << step;
+            let merkle_path_length = (domain_log - step) as u64;
+            proof_size_bytes += n_queries
+                * ((coset_size - 1) * FIELD_ELEMENT_BYTES + merkle_path_length * HASH_BYTES);
+            verifier_cost += n_queries * (coset_size + (merkle_path_length + 1) * HASH_COST);
+            prover_cost += (1u64 << domain_log) + (1u64 << (domain_log - step)) * HASH_COST;
+            domain_log -= step;
+        }
+
+        let last_layer_degree_bound = fri_parameters.last_layer_degree_bound as u64;
+        proof_size_bytes += last_layer_degree_bound * FIELD_ELEMENT_BYTES;
+        // The verifier evaluates the last layer polynomial for each query.
+        verifier_cost += n_queries * last_layer_degree_bound;
+        // The prover interpolates the last layer with a FFT.
+        prover_cost += (1u64 << domain_log) * domain_log as u64;
+
+        FriCostEstimate {
+            proof_size_bytes,
+            verifier_cost,
+            prover_cost,
+        }
+    }
+}
+
+/// Lists the partitions of `n` into parts of at most `max_part`, in decreasing order.
+fn decreasing_partitions(n: u32, max_part: u32, current: &mut Vec<u32>, out: &mut Vec<Vec<u32>>) {
+    if n == 0 {
+        if !current.is_empty() {
+            out.push(current.clone());
+        }
+        return;
+    }
+    for part in (1..=max_part.min(n)).rev() {
+        current.push(part);
+        decreasing_partitions(n - part, part, current, out);
+        current.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use std::collections::HashMap;
+
+    use crate::fri::{
+        generate_prover_parameters, CairoVerifierFriComputer, DefaultFriComputer, FriComputer,
+        L1VerifierFriComputer,
+    };
+    use crate::models::{
+        Layout, ProverParameters, PublicInput, StarkParameters, CPU_COMPONENT_HEIGHT,
+    };
+    use crate::params_validation::validate_prover_parameters_for_verifier;
+
+    use super::*;
+
+    /// Returns a public input with a trace of `trace_length` rows.
+    fn public_input(trace_length: u32) -> PublicInput {
+        PublicInput {
+            layout: Layout::Recursive,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: trace_length / CPU_COMPONENT_HEIGHT,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: None,
+        }
+    }
+
+    #[rstest]
+    fn test_decreasing_partitions() {
+        let mut partitions = vec![];
+        decreasing_partitions(4, 2, &mut vec![], &mut partitions);
+        assert_eq!(
+            partitions,
+            vec![vec![2, 2], vec![2, 1, 1], vec![1, 1, 1, 1]]
+        );
+    }
+
+    #[rstest]
+    #[case(Verifier::Stone, 524288)]
+    #[case(Verifier::Stone, 16384)]
+    #[case(Verifier::L1, 524288)]
+    #[case(Verifier::L1, 16384)]
+    #[case(Verifier::Cairo, 524288)]
+    #[case(Verifier::Cairo, 1 << 22)]
+    fn test_candidates_are_valid(#[case] verifier: Verifier, #[case] trace_length: u32) {
+        let optimizer = FriOptimizer::new(verifier.clone());
+        let candidates = optimizer.candidates(trace_length);
+        assert!(!candidates.is_empty());
+
+        let public_input = public_input(trace_length);
+        let parameters = generate_prover_parameters(public_input.n_steps, verifier.clone());
+        for candidate in &candidates {
+            let parameters = ProverParameters {
+                stark: StarkParameters {
+                    fri: candidate.fri_parameters.clone(),
+                    ..parameters.stark.clone()
+                },
+                ..parameters.clone()
+            };
+            assert_eq!(
+                validate_prover_parameters_for_verifier(&public_input, &parameters, &verifier),
+                Ok(()),
+                "invalid candidate: {:?}",
+                candidate.fri_parameters
+            );
+            assert!(
+                candidate.fri_parameters.last_layer_degree_bound <= DEFAULT_LAST_LAYER_DEGREE_BOUND
+            );
+        }
+    }
+
+    /// Check that the candidates respect the limits of the Cairo verifier when the optimizer
+    /// allows more.
+    #[rstest]
+    fn test_candidates_respect_cairo_verifier_limits() {
+        let optimizer = FriOptimizer {
+            max_step_value: 1,
+            max_last_layer_degree_bound: 1 << 20,
+            ..FriOptimizer::new(Verifier::Cairo)
+        };
+        let candidates = optimizer.candidates(1 << 22);
+        assert!(!candidates.is_empty());
+
+        for candidate in &candidates {
+            let fri_parameters = &candidate.fri_parameters;
+            assert!(fri_parameters.fri_step_list.len() <= 15);
+            assert!(fri_parameters.last_layer_degree_bound <= 1 << 15);
+        }
+    }
+
+    #[rstest]
+    fn test_candidates_no_steps() {
+        assert!(FriOptimizer::new(Verifier::Stone).candidates(0).is_empty());
+    }
+
+    fn permutations(steps: &[u32]) -> Vec<Vec<u32>> {
+        if steps.is_empty() {
+            return vec![vec![]];
+        }
+        let mut out = vec![];
+        for index in 0..steps.len() {
+            let mut rest = steps.to_vec();
+            let step = rest.remove(index);
+            for mut permutation in permutations(&rest) {
+                permutation.insert(0, step);
+                out.push(permutation);
+            }
+        }
+        out
+    }
+
+    /// Check that no other order of the steps of a candidate is cheaper.
+    #[rstest]
+    #[case(Verifier::Stone)]
+    #[case(Verifier::L1)]
+    fn test_candidates_use_the_cheapest_order(#[case] verifier: Verifier) {
+        let optimizer = FriOptimizer::new(verifier.clone());
+        let trace_length = 256;
+        for candidate in optimizer.candidates(trace_length) {
+            let fri_step_list = &candidate.fri_parameters.fri_step_list;
+            // The leading 0 of the L1 verifier does not change the costs.
+            let steps = &fri_step_list[(fri_step_list[0] == 0) as usize..];
+            for permutation in permutations(steps) {
+                if verifier == Verifier::L1 && permutation.last() == Some(&1) {
+                    continue;
+                }
+                let fri_parameters = FriParameters {
+                    fri_step_list: permutation,
+                    ..candidate.fri_parameters.clone()
+                };
+                let estimate = optimizer.estimate(trace_length, &fri_parameters);
+                for objective in [
+                    FriObjective::ProofSize,
+                    FriObjective::VerifierCost,
+                    FriObjective::ProverTime,
+                ] {
+                    assert!(estimate.cost(objective) >= candidate.estimate.cost(objective));
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(Verifier::Stone, 32768)]
+    #[case(Verifier::L1, 524288)]
+    #[case(Verifier::Cairo, 32768)]
+    fn test_candidates_include_greedy_parameters(
+        #[case] verifier: Verifier,
+        #[case] nb_steps: u32,
+    ) {
+        let greedy = match verifier {
+            Verifier::Stone => DefaultFriComputer.compute_fri_parameters(nb_steps),
+            Verifier::L1 => L1VerifierFriComputer.compute_fri_parameters(nb_steps),
+            Verifier::Cairo => CairoVerifierFriComputer.compute_fri_parameters(nb_steps),
+        };
+        let candidates = FriOptimizer::new(verifier).candidates(nb_steps * CPU_COMPONENT_HEIGHT);
+        assert!(candidates
+            .iter()
+            .any(|candidate| candidate.fri_parameters == greedy));
+    }
+
+    #[rstest]
+    fn test_best_per_objective() {
+        let optimizer = FriOptimizer::new(Verifier::Stone);
+        let trace_length = 524288;
+        let candidates = optimizer.candidates(trace_length);
+
+        for objective in [
+            FriObjective::ProofSize,
+            FriObjective::VerifierCost,
+            FriObjective::ProverTime,
+        ] {
+            let best = optimizer.best(trace_length, objective).unwrap();
+            let best_cost = optimizer.estimate(trace_length, &best).cost(objective);
+            assert!(candidates
+                .iter()
+                .all(|candidate| candidate.estimate.cost(objective) >= best_cost));
+        }
+    }
+
+    #[rstest]
+    fn test_estimate_prefers_larger_steps_for_prover_time() {
+        let optimizer = FriOptimizer::new(Verifier::Stone);
+        let best = optimizer.best(524288, FriObjective::ProverTime).unwrap();
+        // Folding more at once means fewer layers to commit to.
+        assert_eq!(best.fri_step_list[0], optimizer.max_step_value);
+    }
+}
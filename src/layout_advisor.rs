@@ -0,0 +1,388 @@
+use std::fmt::{Display, Formatter};
+
+use cairo_vm::cairo_run::CairoRunConfig;
+use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
+use cairo_vm::vm::errors::runner_errors::RunnerError;
+use thiserror::Error;
+
+use crate::cairo_vm::get_execution_resources_report;
+use crate::models::{ExecutionResourcesReport, Layout, CPU_COMPONENT_HEIGHT};
+
+/// Layouts considered by the advisor. Layouts with the same proving cost are ranked in this
+/// order.
+const CANDIDATE_LAYOUTS: [Layout; 10] = [
+    Layout::Plain,
+    Layout::Small,
+    Layout::Dex,
+    Layout::Recursive,
+    Layout::RecursiveLargeOutput,
+    Layout::RecursiveWithPoseidon,
+    Layout::AllSolidity,
+    Layout::Starknet,
+    Layout::StarknetWithKeccak,
+    Layout::AllCairo,
+];
+
+/// Layout used for dry runs. It must support every builtin so that the dry run succeeds for
+/// any program.
+const DRY_RUN_LAYOUT: Layout = Layout::AllCairo;
+
+/// Builtins that do not use trace cells and are therefore supported by all layouts.
+const VIRTUAL_BUILTINS: [&str; 1] = ["segment_arena"];
+
+/// Reason why a layout cannot be used to prove a program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutRejection {
+    /// The program uses a builtin that the layout does not provide.
+    MissingBuiltin(String),
+    /// The program declares a builtin that the layout does not provide. The builtin is not used,
+    /// so the layout would be accepted with `allow_missing_builtins`.
+    MissingUnusedBuiltin(String),
+}
+
+impl Display for LayoutRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingBuiltin(builtin) => write!(f, "missing builtin {builtin}"),
+            Self::MissingUnusedBuiltin(builtin) => write!(
+                f,
+                "missing builtin {builtin} (unused, requires allow_missing_builtins)"
+            ),
+        }
+    }
+}
+
+/// A layout able to prove a program, with the cost of the proof.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutCandidate {
+    pub layout: Layout,
+    /// Trace length (in steps) required to prove the program with this layout.
+    pub padded_n_steps: usize,
+    /// Number of cells of the trace, i.e. the number of rows times the number of columns
+    /// of the layout. This is the main driver of the proving cost.
+    pub trace_cells: u64,
+    /// Builtin that determines the trace length. `None` if the number of steps of the program
+    /// is the limiting factor.
+    pub limiting_builtin: Option<String>,
+}
+
+/// Layout recommended by `recommend_layout`, with the reasoning behind the choice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutRecommendation {
+    pub layout: Layout,
+    /// Trace length (in steps) required to prove the program with this layout.
+    pub padded_n_steps: usize,
+    /// Number of cells of the trace with this layout.
+    pub trace_cells: u64,
+    /// Builtin that determines the trace length. `None` if the number of steps of the program
+    /// is the limiting factor.
+    pub limiting_builtin: Option<String>,
+    /// Compatible layouts that were not selected.
+    pub alternatives: Vec<LayoutCandidate>,
+    /// Layouts that cannot prove the program.
+    pub rejected: Vec<(Layout, LayoutRejection)>,
+}
+
+impl LayoutRecommendation {
+    /// Human-readable explanation of the choice.
+    pub fn explanation(&self) -> String {
+        let limiting_factor = match &self.limiting_builtin {
+            Some(builtin) => format!("the usage of the {builtin} builtin"),
+            None => "the number of steps".to_string(),
+        };
+        let mut explanation = format!(
+            "Selected layout {} with a trace of {} steps ({} cells), determined by {}.",
+            self.layout, self.padded_n_steps, self.trace_cells, limiting_factor
+        );
+
+        for candidate in &self.alternatives {
+            explanation.push_str(&format!(
+                "\n{} is compatible but requires {} steps ({} cells).",
+                candidate.layout, candidate.padded_n_steps, candidate.trace_cells
+            ));
+        }
+        for (layout, rejection) in &self.rejected {
+            explanation.push_str(&format!("\n{layout} is not compatible: {rejection}."));
+        }
+
+        explanation
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LayoutAdvisorError {
+    #[error("no layout can prove the program")]
+    NoCompatibleLayout(Vec<(Layout, LayoutRejection)>),
+    #[error(transparent)]
+    DryRun(#[from] CairoRunError),
+    #[error(transparent)]
+    ExecutionResources(#[from] RunnerError),
+}
+
+/// Checks whether `layout` can prove the program and computes the cost of the proof.
+fn evaluate_layout(
+    layout: &Layout,
+    resources: &ExecutionResourcesReport,
+    allow_missing_builtins: bool,
+) -> Result<LayoutCandidate, LayoutRejection> {
+    let mut required_n_steps = resources.n_steps;
+    let mut limiting_builtin = None;
+
+    // Sort builtins to keep the result deterministic.
+    let mut builtins: Vec<_> = resources.builtins.iter().collect();
+    builtins.sort_by_key(|(name, _)| name.as_str());
+
+    for (builtin, usage) in builtins {
+        if VIRTUAL_BUILTINS.contains(&builtin.as_str()) {
+            continue;
+        }
+        if !layout.has_builtin(builtin) {
+            if usage.used_instances > 0 {
+                return Err(LayoutRejection::MissingBuiltin(builtin.clone()));
+            }
+            if !allow_missing_builtins {
+                return Err(LayoutRejection::MissingUnusedBuiltin(builtin.clone()));
+            }
+            continue;
+        }
+
+        if let Some(ratio) = layout.builtin_ratio(builtin) {
+            let builtin_n_steps = usage.used_instances * ratio as usize;
+            if builtin_n_steps > required_n_steps {
+                required_n_steps = builtin_n_steps;
+                limiting_builtin = Some(builtin.clone());
+            }
+        }
+    }
+
+    let padded_n_steps = required_n_steps.next_power_of_two();
+    let layout_params = layout.params();
+    let n_rows = padded_n_steps as u64
+        * CPU_COMPONENT_HEIGHT as u64
+        * layout_params.cpu_component_step as u64;
+
+    Ok(LayoutCandidate {
+        layout: layout.clone(),
+        padded_n_steps,
+        trace_cells: n_rows * layout_params.n_columns as u64,
+        limiting_builtin,
+    })
+}
+
+/// Chooses the cheapest layout able to prove a program.
+///
+/// Layouts are compared by the number of cells of the trace they require, which is the main
+/// driver of the proving cost.
+///
+/// * `resources`: Resources used by the program, typically obtained through `dry_run`.
+///   The layout used to obtain the report does not matter, but `resources.n_steps` must
+///   not include the padding of the trace.
+/// * `allow_missing_builtins`: Whether the program will be run with `allow_missing_builtins`.
+pub fn recommend_layout(
+    resources: &ExecutionResourcesReport,
+    allow_missing_builtins: bool,
+) -> Result<LayoutRecommendation, LayoutAdvisorError> {
+    let mut compatible = vec![];
+    let mut rejected = vec![];
+
+    for layout in CANDIDATE_LAYOUTS {
+        match evaluate_layout(&layout, resources, allow_missing_builtins) {
+            Ok(candidate) => compatible.push(candidate),
+            Err(rejection) => rejected.push((layout, rejection)),
+        }
+    }
+
+    // `min_by_key` returns the first minimum, so ties are broken by the order of
+    // `CANDIDATE_LAYOUTS`.
+    let best_index = compatible
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| candidate.trace_cells)
+        .map(|(index, _)| index)
+        .ok_or_else(|| LayoutAdvisorError::NoCompatibleLayout(rejected.clone()))?;
+
+    let best = compatible.remove(best_index);
+
+    Ok(LayoutRecommendation {
+        layout: best.layout,
+        padded_n_steps: best.padded_n_steps,
+        trace_cells: best.trace_cells,
+        limiting_builtin: best.limiting_builtin,
+        alternatives: compatible,
+        rejected,
+    })
+}
+
+/// Runs a Cairo program in proof mode to measure the resources it uses.
+///
+/// The program is run with a layout that supports all builtins, without padding the trace
+/// so that the number of steps does not depend on this layout. The resulting report can be
+/// passed to `recommend_layout`.
+///
+/// * `program_content`: Compiled program content.
+/// * `allow_missing_builtins`: Whether to allow builtins that the layout does not support.
+pub fn dry_run(
+    program_content: &[u8],
+    allow_missing_builtins: Option<bool>,
+) -> Result<ExecutionResourcesReport, LayoutAdvisorError> {
+    let cairo_run_config = CairoRunConfig {
+        entrypoint: "main",
+        trace_enabled: true,
+        relocate_mem: true,
+        layout: &DRY_RUN_LAYOUT.to_string(),
+        proof_mode: true,
+        secure_run: None,
+        disable_trace_padding: true,
+        allow_missing_builtins,
+    };
+    let mut hint_processor = BuiltinHintProcessor::new_empty();
+    let (cairo_runner, vm) =
+        cairo_vm::cairo_run::cairo_run(program_content, &cairo_run_config, &mut hint_processor)?;
+
+    let n_steps = cairo_runner
+        .relocated_trace
+        .as_ref()
+        .map(|trace| trace.len())
+        .unwrap_or(vm.current_step);
+    let report = get_execution_resources_report(
+        &cairo_runner,
+        &vm,
+        DRY_RUN_LAYOUT,
+        n_steps.next_power_of_two(),
+    )?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use crate::models::BuiltinUsage;
+    use crate::test_utils::{parsed_prover_test_case, ParsedProverTestCase};
+
+    use super::*;
+
+    fn make_report(n_steps: usize, builtins: &[(&str, usize)]) -> ExecutionResourcesReport {
+        let builtins = builtins
+            .iter()
+            .map(|(name, used_instances)| {
+                (
+                    name.to_string(),
+                    BuiltinUsage::new(*used_instances, n_steps, None),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        ExecutionResourcesReport {
+            layout: DRY_RUN_LAYOUT,
+            n_steps,
+            padded_n_steps: n_steps.next_power_of_two(),
+            n_memory_holes: 0,
+            builtins,
+        }
+    }
+
+    #[rstest]
+    #[case(make_report(1000, &[]), Layout::Plain, 1024, None)]
+    #[case(
+        make_report(1000, &[("output", 1)]),
+        Layout::RecursiveWithPoseidon,
+        1024,
+        None
+    )]
+    #[case(
+        make_report(1000, &[("output", 1), ("pedersen", 200)]),
+        Layout::Dex,
+        2048,
+        Some("pedersen")
+    )]
+    #[case(
+        make_report(1000, &[("output", 1), ("ecdsa", 1)]),
+        Layout::Starknet,
+        2048,
+        Some("ecdsa")
+    )]
+    #[case(
+        make_report(1000, &[("keccak", 1)]),
+        Layout::AllCairo,
+        2048,
+        Some("keccak")
+    )]
+    fn test_recommend_layout(
+        #[case] resources: ExecutionResourcesReport,
+        #[case] expected_layout: Layout,
+        #[case] expected_n_steps: usize,
+        #[case] expected_limiting_builtin: Option<&str>,
+    ) {
+        let recommendation = recommend_layout(&resources, false).unwrap();
+
+        assert_eq!(recommendation.layout, expected_layout);
+        assert_eq!(recommendation.padded_n_steps, expected_n_steps);
+        assert_eq!(
+            recommendation.limiting_builtin.as_deref(),
+            expected_limiting_builtin
+        );
+        let n_columns = expected_layout.params().n_columns as u64;
+        assert_eq!(
+            recommendation.trace_cells,
+            expected_n_steps as u64 * 16 * n_columns
+        );
+    }
+
+    #[test]
+    fn test_recommend_layout_prefers_fewer_cells() {
+        // Small has more capacity for Pedersen but Dex has fewer columns.
+        let resources = make_report(1000, &[("output", 1), ("pedersen", 200)]);
+        let recommendation = recommend_layout(&resources, false).unwrap();
+
+        let small = recommendation
+            .alternatives
+            .iter()
+            .find(|candidate| candidate.layout == Layout::Small)
+            .unwrap();
+        assert_eq!(small.padded_n_steps, recommendation.padded_n_steps);
+        assert!(small.trace_cells > recommendation.trace_cells);
+    }
+
+    #[test]
+    fn test_recommend_layout_allow_missing_builtins() {
+        // The program declares the ECDSA builtin but does not use it
+        let resources = make_report(1000, &[("output", 1), ("ecdsa", 0)]);
+
+        let recommendation = recommend_layout(&resources, true).unwrap();
+        assert_eq!(recommendation.layout, Layout::RecursiveWithPoseidon);
+
+        let recommendation = recommend_layout(&resources, false).unwrap();
+        assert_eq!(recommendation.layout, Layout::Starknet);
+    }
+
+    #[rstest]
+    fn test_dry_run_and_recommend_layout(parsed_prover_test_case: ParsedProverTestCase) {
+        let resources = dry_run(&parsed_prover_test_case.compiled_program, None).unwrap();
+        // The dry run does not pad the trace.
+        assert!(resources.n_steps < parsed_prover_test_case.public_input.n_steps as usize);
+
+        let recommendation = recommend_layout(&resources, false).unwrap();
+
+        // Fibonacci only uses the output builtin.
+        assert_eq!(recommendation.layout, Layout::RecursiveWithPoseidon);
+        assert_eq!(recommendation.limiting_builtin, None);
+        assert_eq!(
+            recommendation.padded_n_steps,
+            parsed_prover_test_case.public_input.n_steps as usize
+        );
+    }
+
+    #[test]
+    fn test_recommend_layout_no_compatible_layout() {
+        let resources = make_report(1000, &[("unknown", 1)]);
+        let result = recommend_layout(&resources, true);
+        assert!(matches!(
+            result,
+            Err(LayoutAdvisorError::NoCompatibleLayout(_))
+        ));
+    }
+}
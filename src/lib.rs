@@ -1,8 +1,24 @@
+pub mod api;
+pub mod bundle;
 pub mod cairo_vm;
+pub mod docker;
+pub mod dynamic_params;
 pub mod error;
 pub mod fri;
+pub mod fri_optimizer;
 pub mod json;
+pub mod layout_advisor;
 pub mod models;
+pub mod params_validation;
+pub mod proof_analysis;
+pub mod proof_cache;
+pub mod proof_encoding;
 pub mod prover;
+pub mod prover_config;
+pub mod prover_pool;
+#[cfg(feature = "client")]
+pub mod remote;
+#[cfg(feature = "server")]
+pub mod server;
 pub(crate) mod test_utils;
 pub mod verifier;
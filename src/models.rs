@@ -8,10 +8,12 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Verifier {
     Stone,
     L1,
+    /// Verifiers written in Cairo, ex: the Starknet verifier.
+    Cairo,
 }
 
 impl FromStr for Verifier {
@@ -21,6 +23,7 @@ impl FromStr for Verifier {
         let verifier = match s {
             "stone" => Self::Stone,
             "l1" => Self::L1,
+            "cairo" => Self::Cairo,
             other => {
                 return Err(format!("unknown verifier: {other}"));
             }
@@ -35,18 +38,19 @@ impl Display for Verifier {
         let s = match self {
             Self::Stone => "stone",
             Self::L1 => "l1",
+            Self::Cairo => "cairo",
         };
         write!(f, "{}", s)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct CachedLdeConfig {
     pub store_full_lde: bool,
     pub use_fft_for_eval: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ProverConfig {
     pub cached_lde_config: CachedLdeConfig,
     pub constraint_polynomial_task_size: i32,
@@ -68,7 +72,7 @@ impl Default for ProverConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FriParameters {
     pub fri_step_list: Vec<u32>,
     pub last_layer_degree_bound: u32,
@@ -76,19 +80,276 @@ pub struct FriParameters {
     pub proof_of_work_bits: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct StarkParameters {
     pub fri: FriParameters,
     pub log_n_cosets: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// Field of the prover. The Cairo AIR is defined over the Stark252 field.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Field {
+    /// Field of size 2^251 + 17 * 2^192 + 1.
+    #[default]
+    PrimeField0,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PrimeField0 => write!(f, "PrimeField0"),
+        }
+    }
+}
+
+/// Hash function supported by Stone.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashFunction {
+    Keccak256,
+    /// Blake2s, named `blake256` by Stone.
+    Blake256,
+    Pedersen,
+    Poseidon3,
+}
+
+impl HashFunction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Keccak256 => "keccak256",
+            Self::Blake256 => "blake256",
+            Self::Pedersen => "pedersen",
+            Self::Poseidon3 => "poseidon3",
+        }
+    }
+}
+
+impl FromStr for HashFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value::<Self>(Value::String(s.to_string())).map_err(|e| e.to_string())
+    }
+}
+
+impl Display for HashFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Bits kept by a masked hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskSide {
+    /// Keep the least significant bits.
+    Lsb,
+    /// Keep the most significant bits.
+    Msb,
+}
+
+/// Hash function of the Merkle commitments, optionally masked to a number of bits.
+///
+/// Serialized as Stone names it, ex: "keccak256_masked160_msb" or "poseidon3".
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct CommitmentHash {
+    hash: HashFunction,
+    mask: Option<(u32, MaskSide)>,
+}
+
+impl CommitmentHash {
+    pub const KECCAK256: Self = Self {
+        hash: HashFunction::Keccak256,
+        mask: None,
+    };
+    pub const BLAKE256: Self = Self {
+        hash: HashFunction::Blake256,
+        mask: None,
+    };
+    pub const PEDERSEN: Self = Self {
+        hash: HashFunction::Pedersen,
+        mask: None,
+    };
+    pub const POSEIDON3: Self = Self {
+        hash: HashFunction::Poseidon3,
+        mask: None,
+    };
+    /// Default commitment hash of Stone, used by the L1 verifier.
+    pub const KECCAK256_MASKED160_MSB: Self = Self {
+        hash: HashFunction::Keccak256,
+        mask: Some((160, MaskSide::Msb)),
+    };
+    pub const KECCAK256_MASKED160_LSB: Self = Self {
+        hash: HashFunction::Keccak256,
+        mask: Some((160, MaskSide::Lsb)),
+    };
+    pub const KECCAK256_MASKED248_LSB: Self = Self {
+        hash: HashFunction::Keccak256,
+        mask: Some((248, MaskSide::Lsb)),
+    };
+    pub const BLAKE256_MASKED160_MSB: Self = Self {
+        hash: HashFunction::Blake256,
+        mask: Some((160, MaskSide::Msb)),
+    };
+    pub const BLAKE256_MASKED160_LSB: Self = Self {
+        hash: HashFunction::Blake256,
+        mask: Some((160, MaskSide::Lsb)),
+    };
+    pub const BLAKE256_MASKED248_LSB: Self = Self {
+        hash: HashFunction::Blake256,
+        mask: Some((248, MaskSide::Lsb)),
+    };
+
+    /// Commitment hashes supported by Stone. Other combinations of a hash and a mask are
+    /// rejected by the prover.
+    pub const ALL: [Self; 10] = [
+        Self::KECCAK256,
+        Self::BLAKE256,
+        Self::PEDERSEN,
+        Self::POSEIDON3,
+        Self::KECCAK256_MASKED160_MSB,
+        Self::KECCAK256_MASKED160_LSB,
+        Self::KECCAK256_MASKED248_LSB,
+        Self::BLAKE256_MASKED160_MSB,
+        Self::BLAKE256_MASKED160_LSB,
+        Self::BLAKE256_MASKED248_LSB,
+    ];
+
+    fn supported(self) -> Result<Self, String> {
+        if Self::ALL.contains(&self) {
+            Ok(self)
+        } else {
+            Err(format!("unsupported commitment hash: {self}"))
+        }
+    }
+
+    /// Returns an unmasked commitment hash.
+    pub fn new(hash: HashFunction) -> Result<Self, String> {
+        Self { hash, mask: None }.supported()
+    }
+
+    /// Returns a commitment hash keeping `bits` bits of the output of `hash`, if Stone
+    /// supports this combination.
+    pub fn masked(hash: HashFunction, bits: u32, side: MaskSide) -> Result<Self, String> {
+        Self {
+            hash,
+            mask: Some((bits, side)),
+        }
+        .supported()
+    }
+
+    pub fn hash(&self) -> HashFunction {
+        self.hash
+    }
+
+    /// Number of bits kept and which ones, `None` if the hash is not masked.
+    pub fn mask(&self) -> Option<(u32, MaskSide)> {
+        self.mask
+    }
+}
+
+impl FromStr for CommitmentHash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|commitment_hash| commitment_hash.to_string() == s)
+            .ok_or_else(|| format!("invalid commitment hash: {s}"))
+    }
+}
+
+impl TryFrom<String> for CommitmentHash {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for CommitmentHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.mask {
+            None => write!(f, "{}", self.hash),
+            Some((bits, side)) => {
+                let side = match side {
+                    MaskSide::Lsb => "lsb",
+                    MaskSide::Msb => "msb",
+                };
+                write!(f, "{}_masked{}_{}", self.hash, bits, side)
+            }
+        }
+    }
+}
+
+impl From<CommitmentHash> for String {
+    fn from(value: CommitmentHash) -> Self {
+        value.to_string()
+    }
+}
+
+/// Hash function of the proof of work.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PowHash {
+    Keccak256,
+    /// Blake2s, named `blake256` by Stone.
+    Blake256,
+}
+
+impl Display for PowHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Keccak256 => "keccak256",
+            Self::Blake256 => "blake256",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// Parameters of the statement proven by the prover.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatementParameters {
+    /// Hash function used to compute the hash of the public memory pages.
+    pub page_hash: HashFunction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ProverParameters {
-    pub field: String,
+    pub field: Field,
     pub stark: StarkParameters,
     pub use_extension_field: bool,
+    /// Hash function of the Fiat-Shamir channel. Stone uses its default if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_hash: Option<HashFunction>,
+    /// Hash function of the Merkle commitments. Stone uses its default if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment_hash: Option<CommitmentHash>,
+    /// Hash function of the proof of work. Stone uses its default if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pow_hash: Option<PowHash>,
+    /// Number of Merkle tree layers, from the leaves, hashed with a verifier-friendly hash
+    /// function instead of `commitment_hash`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub n_verifier_friendly_commitment_layers: u32,
+    /// Whether the channel is updated in a way that is cheap to verify in Cairo.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verifier_friendly_channel_updates: bool,
+    /// Whether the commitments use a verifier-friendly hash function.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verifier_friendly_commitment_hashes: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement: Option<StatementParameters>,
 }
 
+/// Layouts supported by the Stone prover.
+///
+/// The `all_cairo_stwo` layout of the Cairo VM is not included: it targets the Stwo prover
+/// and has no AIR definition in Stone.
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
 pub enum Layout {
     #[serde(rename = "plain")]
@@ -109,6 +370,10 @@ pub enum Layout {
     AllSolidity,
     #[serde(rename = "starknet_with_keccak")]
     StarknetWithKeccak,
+    #[serde(rename = "recursive_with_poseidon")]
+    RecursiveWithPoseidon,
+    #[serde(rename = "dynamic")]
+    Dynamic,
 }
 
 impl FromStr for Layout {
@@ -128,20 +393,315 @@ impl Display for Layout {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// A builtin provided by a layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutBuiltin {
+    /// Builtin name, without the `_builtin` suffix (ex: "range_check").
+    pub name: &'static str,
+    /// Number of Cairo steps per builtin instance. `None` for builtins that do not use a
+    /// fixed ratio, i.e. the output builtin and the builtins of the dynamic layout.
+    pub ratio: Option<u32>,
+}
+
+/// Parameters of the diluted pool of a layout, used by the bitwise and Keccak builtins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DilutedPoolParams {
+    pub units_per_step: u32,
+    pub spacing: u32,
+    pub n_bits: u32,
+}
+
+impl Default for DilutedPoolParams {
+    fn default() -> Self {
+        DEFAULT_DILUTED_POOL
+    }
+}
+
+/// Number of trace rows used by the CPU component of a single Cairo step.
+pub const CPU_COMPONENT_HEIGHT: u32 = 16;
+
+/// Static description of a Stone layout.
+///
+/// These values mirror the layout definitions of the Stone prover and of the Cairo VM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutParams {
+    pub builtins: &'static [LayoutBuiltin],
+    /// Height of the CPU component of a Cairo step, in multiples of `CPU_COMPONENT_HEIGHT`.
+    pub cpu_component_step: u32,
+    /// Number of trace columns, including the interaction columns: `kNumColumnsFirst` +
+    /// `kNumColumnsSecond` in the Stone `cpu_air_definition_<layout>.h` file of the layout.
+    pub n_columns: u32,
+    pub memory_units_per_step: u32,
+    /// Range check units per step.
+    pub rc_units: u32,
+    pub public_memory_fraction: u32,
+    pub diluted_pool: Option<DilutedPoolParams>,
+    /// Whether the layout is parameterized at proving time through `PublicInput.dynamic_params`.
+    pub uses_dynamic_params: bool,
+    /// Verifiers able to verify proofs generated with this layout.
+    pub verifiers: &'static [Verifier],
+}
+
+const fn builtin(name: &'static str, ratio: u32) -> LayoutBuiltin {
+    LayoutBuiltin {
+        name,
+        ratio: Some(ratio),
+    }
+}
+
+const fn dynamic_builtin(name: &'static str) -> LayoutBuiltin {
+    LayoutBuiltin { name, ratio: None }
+}
+
+/// The output builtin, present in all layouts but `plain`.
+const OUTPUT: LayoutBuiltin = dynamic_builtin("output");
+
+const DEFAULT_DILUTED_POOL: DilutedPoolParams = DilutedPoolParams {
+    units_per_step: 16,
+    spacing: 4,
+    n_bits: 16,
+};
+
+const DEFAULT_LAYOUT_PARAMS: LayoutParams = LayoutParams {
+    builtins: &[],
+    cpu_component_step: 1,
+    n_columns: 0,
+    memory_units_per_step: 8,
+    rc_units: 4,
+    public_memory_fraction: 8,
+    diluted_pool: Some(DEFAULT_DILUTED_POOL),
+    uses_dynamic_params: false,
+    verifiers: &[Verifier::Stone, Verifier::L1, Verifier::Cairo],
+};
+
+const PLAIN_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_plain.h: 6 + 2 columns.
+    n_columns: 8,
+    rc_units: 16,
+    public_memory_fraction: 4,
+    diluted_pool: None,
+    verifiers: &[Verifier::Stone],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const SMALL_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_small.h: 23 + 2 columns.
+    n_columns: 25,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 8),
+        builtin("range_check", 8),
+        builtin("ecdsa", 512),
+    ],
+    rc_units: 16,
+    public_memory_fraction: 4,
+    diluted_pool: None,
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const DEX_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_dex.h: 21 + 1 columns.
+    n_columns: 22,
+    rc_units: 4,
+    ..SMALL_PARAMS
+};
+
+const RECURSIVE_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_recursive.h: 7 + 3 columns.
+    n_columns: 10,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 128),
+        builtin("range_check", 8),
+        builtin("bitwise", 8),
+    ],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const STARKNET_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_starknet.h: 9 + 1 columns.
+    n_columns: 10,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 32),
+        builtin("range_check", 16),
+        builtin("ecdsa", 2048),
+        builtin("bitwise", 64),
+        builtin("ec_op", 1024),
+        builtin("poseidon", 32),
+    ],
+    diluted_pool: Some(DilutedPoolParams {
+        units_per_step: 2,
+        ..DEFAULT_DILUTED_POOL
+    }),
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const STARKNET_WITH_KECCAK_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_starknet_with_keccak.h: 12 + 3 columns.
+    n_columns: 15,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 32),
+        builtin("range_check", 16),
+        builtin("ecdsa", 2048),
+        builtin("bitwise", 64),
+        builtin("ec_op", 1024),
+        builtin("keccak", 2048),
+        builtin("poseidon", 32),
+    ],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const RECURSIVE_LARGE_OUTPUT_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_recursive_large_output.h: 10 + 3 columns.
+    n_columns: 13,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 128),
+        builtin("range_check", 8),
+        builtin("bitwise", 8),
+        builtin("poseidon", 8),
+    ],
+    verifiers: &[Verifier::Stone, Verifier::L1],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const RECURSIVE_WITH_POSEIDON_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_recursive_with_poseidon.h: 6 + 2 columns.
+    n_columns: 8,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 256),
+        builtin("range_check", 16),
+        builtin("bitwise", 16),
+        builtin("poseidon", 64),
+    ],
+    diluted_pool: Some(DilutedPoolParams {
+        units_per_step: 8,
+        ..DEFAULT_DILUTED_POOL
+    }),
+    verifiers: &[Verifier::Stone, Verifier::Cairo],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const ALL_CAIRO_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_all_cairo.h: 9 + 2 columns.
+    n_columns: 11,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 256),
+        builtin("range_check", 8),
+        builtin("ecdsa", 2048),
+        builtin("bitwise", 16),
+        builtin("ec_op", 1024),
+        builtin("keccak", 2048),
+        builtin("poseidon", 256),
+        builtin("range_check96", 8),
+        builtin("add_mod", 128),
+        builtin("mul_mod", 256),
+    ],
+    verifiers: &[Verifier::Stone],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const ALL_SOLIDITY_PARAMS: LayoutParams = LayoutParams {
+    // cpu_air_definition_all_solidity.h: 25 + 2 columns.
+    n_columns: 27,
+    builtins: &[
+        OUTPUT,
+        builtin("pedersen", 8),
+        builtin("range_check", 8),
+        builtin("ecdsa", 512),
+        builtin("bitwise", 256),
+        builtin("ec_op", 256),
+    ],
+    rc_units: 8,
+    verifiers: &[Verifier::Stone, Verifier::L1],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+const DYNAMIC_PARAMS: LayoutParams = LayoutParams {
+    // The columns of the dynamic layout are set at proving time by the `num_columns_first`
+    // and `num_columns_second` dynamic parameters. This is an estimate for a layout using
+    // all the builtins, to size resources before the parameters are known.
+    n_columns: 73,
+    builtins: &[
+        OUTPUT,
+        dynamic_builtin("pedersen"),
+        dynamic_builtin("range_check"),
+        dynamic_builtin("ecdsa"),
+        dynamic_builtin("bitwise"),
+        dynamic_builtin("ec_op"),
+        dynamic_builtin("keccak"),
+        dynamic_builtin("poseidon"),
+        dynamic_builtin("range_check96"),
+        dynamic_builtin("add_mod"),
+        dynamic_builtin("mul_mod"),
+    ],
+    rc_units: 16,
+    uses_dynamic_params: true,
+    verifiers: &[Verifier::Stone],
+    ..DEFAULT_LAYOUT_PARAMS
+};
+
+impl Layout {
+    /// Returns the static description of this layout.
+    pub fn params(&self) -> &'static LayoutParams {
+        match self {
+            Self::Plain => &PLAIN_PARAMS,
+            Self::Small => &SMALL_PARAMS,
+            Self::Dex => &DEX_PARAMS,
+            Self::Recursive => &RECURSIVE_PARAMS,
+            Self::Starknet => &STARKNET_PARAMS,
+            Self::StarknetWithKeccak => &STARKNET_WITH_KECCAK_PARAMS,
+            Self::RecursiveLargeOutput => &RECURSIVE_LARGE_OUTPUT_PARAMS,
+            Self::RecursiveWithPoseidon => &RECURSIVE_WITH_POSEIDON_PARAMS,
+            Self::AllCairo => &ALL_CAIRO_PARAMS,
+            Self::AllSolidity => &ALL_SOLIDITY_PARAMS,
+            Self::Dynamic => &DYNAMIC_PARAMS,
+        }
+    }
+
+    /// Returns the number of Cairo steps per instance of the specified builtin in this layout.
+    ///
+    /// Returns `None` if the layout does not include the builtin or if the builtin does not
+    /// use a fixed ratio (ex: the output builtin).
+    ///
+    /// * `builtin`: Builtin name, without the `_builtin` suffix (ex: "range_check").
+    pub fn builtin_ratio(&self, builtin: &str) -> Option<u32> {
+        self.params()
+            .builtins
+            .iter()
+            .find(|layout_builtin| layout_builtin.name == builtin)
+            .and_then(|layout_builtin| layout_builtin.ratio)
+    }
+
+    /// Returns whether this layout provides the specified builtin.
+    ///
+    /// * `builtin`: Builtin name, without the `_builtin` suffix (ex: "range_check").
+    pub fn has_builtin(&self, builtin: &str) -> bool {
+        self.params()
+            .builtins
+            .iter()
+            .any(|layout_builtin| layout_builtin.name == builtin)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MemorySegmentAddresses {
     pub begin_addr: u32,
     pub stop_ptr: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PublicMemoryEntry {
     pub address: u32,
     pub value: String,
     pub page: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PublicInput {
     pub layout: Layout,
     pub rc_min: u32,
@@ -149,9 +709,31 @@ pub struct PublicInput {
     pub n_steps: u32,
     pub memory_segments: HashMap<String, MemorySegmentAddresses>,
     pub public_memory: Vec<PublicMemoryEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dynamic_params: Option<HashMap<String, u32>>,
 }
 
+impl PublicInput {
+    /// Returns the CPU component step of the execution, read from the dynamic parameters
+    /// for the dynamic layout.
+    pub fn cpu_component_step(&self) -> u32 {
+        self.dynamic_params
+            .as_ref()
+            .and_then(|dynamic_params| dynamic_params.get("cpu_component_step").copied())
+            .unwrap_or_else(|| self.layout.params().cpu_component_step)
+    }
+
+    /// Returns the number of rows of the execution trace, which determines the size of
+    /// the evaluation domain of the prover.
+    ///
+    /// Returns `None` if the trace length does not fit in 32 bits.
+    pub fn trace_length(&self) -> Option<u32> {
+        self.n_steps
+            .checked_mul(CPU_COMPONENT_HEIGHT)?
+            .checked_mul(self.cpu_component_step())
+    }
+}
+
 // TODO: implement Deserialize in cairo-vm types.
 impl<'a> TryFrom<cairo_vm::air_public_input::PublicInput<'a>> for PublicInput {
     type Error = serde_json::Error;
@@ -169,6 +751,53 @@ impl<'a> TryFrom<cairo_vm::air_public_input::PublicInput<'a>> for PublicInput {
     }
 }
 
+/// Usage of a single builtin during a program execution.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuiltinUsage {
+    /// Number of builtin instances used by the program.
+    pub used_instances: usize,
+    /// Number of builtin instances that fit in the trace for the layout. `None` if the layout
+    /// does not define a ratio for this builtin.
+    pub capacity: Option<usize>,
+    /// Fraction of the layout capacity used by the program. A value above 1 means that the
+    /// trace is too short for the program to fit in the layout.
+    pub usage_ratio: Option<f64>,
+}
+
+impl BuiltinUsage {
+    /// Computes the usage of a builtin relative to the capacity of the layout.
+    ///
+    /// * `used_instances`: Number of builtin instances used by the program.
+    /// * `n_steps`: Number of steps of the trace, after padding.
+    /// * `ratio`: Number of steps per builtin instance in the layout, if any.
+    pub fn new(used_instances: usize, n_steps: usize, ratio: Option<u32>) -> Self {
+        let capacity = ratio.map(|ratio| n_steps / ratio as usize);
+        // Computed from the ratio directly to avoid dividing by a capacity of 0 for short traces.
+        let usage_ratio = ratio
+            .filter(|_| n_steps > 0)
+            .map(|ratio| (used_instances as f64 * ratio as f64) / n_steps as f64);
+
+        Self {
+            used_instances,
+            capacity,
+            usage_ratio,
+        }
+    }
+}
+
+/// Summary of the resources used by a program execution, relative to the layout it ran with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExecutionResourcesReport {
+    pub layout: Layout,
+    /// Number of steps executed by the program.
+    pub n_steps: usize,
+    /// Number of steps of the trace, padded to the next power of two in proof mode.
+    pub padded_n_steps: usize,
+    pub n_memory_holes: usize,
+    /// Builtin usage, indexed by builtin name (ex: "range_check").
+    pub builtins: HashMap<String, BuiltinUsage>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProofVersion {
     commit_hash: String,
@@ -186,9 +815,40 @@ pub struct Proof {
     pub split_proofs: Option<SplitProofs>,
 }
 
+/// Memory or trace file generated by a program execution.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Artifact {
+    /// Content of the artifact, held in memory.
+    InMemory(Vec<u8>),
+    /// Path to the file containing the artifact.
+    OnDisk(PathBuf),
+}
+
+impl From<Vec<u8>> for Artifact {
+    fn from(value: Vec<u8>) -> Self {
+        Self::InMemory(value)
+    }
+}
+
+/// Determines where the prover files are written and when they are deleted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum WorkingDirectoryPolicy {
+    /// Write the files in a temporary directory, deleted with the working directory object.
+    #[default]
+    TempAutoDelete,
+    /// Write the files in a temporary directory, kept if the prover fails.
+    KeepOnFailure,
+    /// Write the files in a temporary directory that is never deleted.
+    KeepAlways,
+    /// Write the files in the specified directory, created if needed. It is never deleted.
+    UserSupplied(PathBuf),
+}
+
 #[derive(Debug)]
 pub struct ProverWorkingDirectory {
-    pub dir: tempfile::TempDir,
+    pub dir: PathBuf,
+    /// Whether `dir` is deleted when this object is dropped.
+    pub delete_on_drop: bool,
     pub public_input_file: PathBuf,
     pub private_input_file: PathBuf,
     pub _memory_file: PathBuf,
@@ -196,10 +856,44 @@ pub struct ProverWorkingDirectory {
     pub prover_config_file: PathBuf,
     pub prover_parameter_file: PathBuf,
     pub proof_file: PathBuf,
+    /// Standard error of the prover, written if the prover fails and the directory is kept.
+    pub stderr_log_file: PathBuf,
     pub annotations_file: Option<PathBuf>,
     pub extra_annotations_file: Option<PathBuf>,
 }
 
+impl ProverWorkingDirectory {
+    /// Creates the working directory object for `dir`, using the standard file names.
+    ///
+    /// * `dir`: Directory containing the prover files.
+    /// * `delete_on_drop`: Whether `dir` is deleted when this object is dropped.
+    pub fn new(dir: PathBuf, delete_on_drop: bool) -> Self {
+        Self {
+            public_input_file: dir.join("public_input.json"),
+            private_input_file: dir.join("private_input.json"),
+            _memory_file: dir.join("memory.bin"),
+            _trace_file: dir.join("trace.bin"),
+            prover_config_file: dir.join("prover_config_file.json"),
+            prover_parameter_file: dir.join("parameters.json"),
+            proof_file: dir.join("proof.json"),
+            stderr_log_file: dir.join("prover_stderr.log"),
+            annotations_file: None,
+            extra_annotations_file: None,
+            dir,
+            delete_on_drop,
+        }
+    }
+}
+
+impl Drop for ProverWorkingDirectory {
+    fn drop(&mut self) {
+        if self.delete_on_drop {
+            // Nothing can be done about an error at this point, ignore it.
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
 /// A struct representing the annotations artifacts generated by running the verifier with
 /// --annotation_file and --extra_output_file
 /// TODO: this is intermediate and probably doesn't need to be exposed (esp. serialized)
@@ -209,6 +903,13 @@ pub struct ProofAnnotations {
     pub extra_output_file: PathBuf,
 }
 
+/// Annotations generated by the verifier, one entry per line of the annotation files.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VerifierAnnotations {
+    pub annotations: Vec<String>,
+    pub extra_annotations: Vec<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_utils::load_test_case_file;
@@ -242,16 +943,228 @@ mod tests {
     #[rstest]
     #[case("small", Layout::Small)]
     #[case("starknet_with_keccak", Layout::StarknetWithKeccak)]
+    #[case("recursive_with_poseidon", Layout::RecursiveWithPoseidon)]
+    #[case("dynamic", Layout::Dynamic)]
     fn deserialize_layout(#[case] layout_str: String, #[case] expected: Layout) {
         let layout = Layout::from_str(&layout_str).unwrap();
         assert_eq!(layout, expected);
     }
 
+    #[rstest]
+    #[case(Layout::StarknetWithKeccak, "keccak", Some(2048))]
+    #[case(Layout::Small, "range_check", Some(8))]
+    #[case(Layout::Small, "bitwise", None)]
+    #[case(Layout::Plain, "output", None)]
+    #[case(Layout::Dynamic, "pedersen", None)]
+    fn layout_builtin_ratio(
+        #[case] layout: Layout,
+        #[case] builtin: &str,
+        #[case] expected: Option<u32>,
+    ) {
+        assert_eq!(layout.builtin_ratio(builtin), expected);
+    }
+
+    #[rstest]
+    #[case(Layout::Plain, "output", false)]
+    #[case(Layout::Small, "output", true)]
+    #[case(Layout::Small, "bitwise", false)]
+    #[case(Layout::Dynamic, "bitwise", true)]
+    fn layout_has_builtin(#[case] layout: Layout, #[case] builtin: &str, #[case] expected: bool) {
+        assert_eq!(layout.has_builtin(builtin), expected);
+    }
+
+    #[test]
+    fn layout_params() {
+        let params = Layout::Dynamic.params();
+        assert!(params.uses_dynamic_params);
+        assert_eq!(params.verifiers, [Verifier::Stone]);
+
+        let params = Layout::Starknet.params();
+        assert!(!params.uses_dynamic_params);
+        assert_eq!(params.rc_units, 4);
+        assert_eq!(params.diluted_pool.as_ref().unwrap().units_per_step, 2);
+    }
+
+    #[rstest]
+    #[case(Layout::Plain, 8)]
+    #[case(Layout::Small, 25)]
+    #[case(Layout::Dex, 22)]
+    #[case(Layout::Recursive, 10)]
+    #[case(Layout::Starknet, 10)]
+    #[case(Layout::StarknetWithKeccak, 15)]
+    #[case(Layout::RecursiveLargeOutput, 13)]
+    #[case(Layout::RecursiveWithPoseidon, 8)]
+    #[case(Layout::AllCairo, 11)]
+    #[case(Layout::AllSolidity, 27)]
+    fn layout_n_columns(#[case] layout: Layout, #[case] expected: u32) {
+        assert_eq!(layout.params().n_columns, expected);
+    }
+
+    #[rstest]
+    #[case(10, 1024, Some(16), Some(64), Some(10.0 / 64.0))]
+    #[case(0, 1024, None, None, None)]
+    #[case(1, 8, Some(16), Some(0), Some(2.0))]
+    fn builtin_usage(
+        #[case] used_instances: usize,
+        #[case] n_steps: usize,
+        #[case] ratio: Option<u32>,
+        #[case] expected_capacity: Option<usize>,
+        #[case] expected_usage_ratio: Option<f64>,
+    ) {
+        let usage = BuiltinUsage::new(used_instances, n_steps, ratio);
+        assert_eq!(usage.capacity, expected_capacity);
+        assert_eq!(usage.usage_ratio, expected_usage_ratio);
+    }
+
     #[rstest]
     #[case(Layout::Small, "small")]
     #[case(Layout::StarknetWithKeccak, "starknet_with_keccak")]
+    #[case(Layout::RecursiveWithPoseidon, "recursive_with_poseidon")]
     fn serialize_layout(#[case] layout: Layout, #[case] expected: &str) {
         let layout_str = layout.to_string();
         assert_eq!(layout_str, expected);
     }
+
+    #[rstest]
+    #[case(Verifier::Stone, "stone")]
+    #[case(Verifier::L1, "l1")]
+    #[case(Verifier::Cairo, "cairo")]
+    fn verifier_string_round_trip(#[case] verifier: Verifier, #[case] expected: &str) {
+        assert_eq!(verifier.to_string(), expected);
+        assert_eq!(Verifier::from_str(expected).unwrap(), verifier);
+    }
+
+    #[rstest]
+    #[case(Layout::Small, None, Some(16384))]
+    #[case(Layout::Dynamic, Some(1), Some(16384))]
+    #[case(Layout::Dynamic, Some(4), Some(65536))]
+    #[case::overflow(Layout::Dynamic, Some(1 << 18), None)]
+    fn public_input_trace_length(
+        #[case] layout: Layout,
+        #[case] cpu_component_step: Option<u32>,
+        #[case] expected: Option<u32>,
+    ) {
+        let public_input = PublicInput {
+            layout,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: 1024,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: cpu_component_step
+                .map(|step| HashMap::from([("cpu_component_step".to_string(), step)])),
+        };
+        assert_eq!(public_input.trace_length(), expected);
+    }
+
+    #[test]
+    fn deserialize_prover_parameters_with_hash_options() {
+        let parameters_json = serde_json::json!({
+            "field": "PrimeField0",
+            "channel_hash": "poseidon3",
+            "commitment_hash": "blake256_masked160_lsb",
+            "n_verifier_friendly_commitment_layers": 9999,
+            "pow_hash": "blake256",
+            "statement": {"page_hash": "pedersen"},
+            "stark": {
+                "fri": {
+                    "fri_step_list": [0, 4, 4, 4],
+                    "last_layer_degree_bound": 128,
+                    "n_queries": 16,
+                    "proof_of_work_bits": 32
+                },
+                "log_n_cosets": 3
+            },
+            "use_extension_field": false,
+            "verifier_friendly_channel_updates": true,
+            "verifier_friendly_commitment_hashes": true
+        });
+        let parameters: ProverParameters = serde_json::from_value(parameters_json.clone()).unwrap();
+
+        assert_eq!(
+            parameters.commitment_hash,
+            Some(CommitmentHash::BLAKE256_MASKED160_LSB)
+        );
+        assert_eq!(parameters.n_verifier_friendly_commitment_layers, 9999);
+        assert_eq!(
+            parameters.statement,
+            Some(StatementParameters {
+                page_hash: HashFunction::Pedersen
+            })
+        );
+        assert_eq!(serde_json::to_value(&parameters).unwrap(), parameters_json);
+    }
+
+    #[rstest]
+    #[case("keccak256_masked160_msb", CommitmentHash::KECCAK256_MASKED160_MSB)]
+    #[case("blake256_masked160_lsb", CommitmentHash::BLAKE256_MASKED160_LSB)]
+    #[case("poseidon3", CommitmentHash::POSEIDON3)]
+    #[case("keccak256", CommitmentHash::KECCAK256)]
+    #[case("blake256", CommitmentHash::BLAKE256)]
+    #[case(
+        "keccak256_masked248_lsb",
+        CommitmentHash::masked(HashFunction::Keccak256, 248, MaskSide::Lsb).unwrap()
+    )]
+    #[case(
+        "blake256_masked248_lsb",
+        CommitmentHash::masked(HashFunction::Blake256, 248, MaskSide::Lsb).unwrap()
+    )]
+    fn commitment_hash_round_trip(#[case] name: &str, #[case] expected: CommitmentHash) {
+        let commitment_hash = CommitmentHash::from_str(name).unwrap();
+        assert_eq!(commitment_hash, expected);
+        assert_eq!(commitment_hash.to_string(), name);
+    }
+
+    #[rstest]
+    #[case("blake256_masked248_msb")]
+    #[case("keccak256_masked248_msb")]
+    #[case("poseidon3_masked160_lsb")]
+    #[case("pedersen_masked248_lsb")]
+    #[case("keccak256_masked128_lsb")]
+    #[case("keccak256_masked160_mid")]
+    #[case("sha256")]
+    fn invalid_commitment_hash(#[case] name: &str) {
+        assert!(CommitmentHash::from_str(name).is_err());
+        assert!(serde_json::from_value::<CommitmentHash>(Value::String(name.to_string())).is_err());
+    }
+
+    #[rstest]
+    fn commitment_hash_constructors() {
+        assert_eq!(
+            CommitmentHash::new(HashFunction::Keccak256),
+            Ok(CommitmentHash::KECCAK256)
+        );
+        assert!(CommitmentHash::masked(HashFunction::Blake256, 248, MaskSide::Msb).is_err());
+        assert!(CommitmentHash::masked(HashFunction::Poseidon3, 160, MaskSide::Lsb).is_err());
+    }
+
+    #[test]
+    fn deserialize_invalid_prover_parameters() {
+        let parameters = prover_parameters_json();
+        for (key, value) in [
+            ("field", "PrimeField1"),
+            ("pow_hash", "poseidon3"),
+            ("channel_hash", "sha256"),
+        ] {
+            let mut invalid_parameters = parameters.clone();
+            invalid_parameters[key] = Value::String(value.to_string());
+            assert!(serde_json::from_value::<ProverParameters>(invalid_parameters).is_err());
+        }
+    }
+
+    fn prover_parameters_json() -> Value {
+        serde_json::json!({
+            "field": "PrimeField0",
+            "stark": {
+                "fri": {
+                    "fri_step_list": [4, 4, 4, 1],
+                    "last_layer_degree_bound": 64,
+                    "n_queries": 18,
+                    "proof_of_work_bits": 24
+                },
+                "log_n_cosets": 4
+            },
+            "use_extension_field": false
+        })
+    }
 }
@@ -0,0 +1,641 @@
+use thiserror::Error;
+
+use crate::fri::{conjectured_security_bits, FriStepConstraints};
+use crate::models::{
+    CommitmentHash, HashFunction, PowHash, ProverParameters, PublicInput, Verifier,
+};
+
+/// Hashes used by Stone when the parameters do not specify them.
+const STONE_DEFAULT_CHANNEL_HASH: HashFunction = HashFunction::Keccak256;
+const STONE_DEFAULT_COMMITMENT_HASH: CommitmentHash = CommitmentHash::KECCAK256_MASKED160_MSB;
+const STONE_DEFAULT_POW_HASH: PowHash = PowHash::Keccak256;
+
+/// Hashes supported by a verifier.
+struct VerifierHashes {
+    channel_hashes: &'static [HashFunction],
+    commitment_hashes: &'static [CommitmentHash],
+    pow_hashes: &'static [PowHash],
+    verifier_friendly_channel_updates: bool,
+}
+
+/// The L1 verifier only implements the default hashes of Stone.
+const L1_VERIFIER_HASHES: VerifierHashes = VerifierHashes {
+    channel_hashes: &[HashFunction::Keccak256],
+    commitment_hashes: &[CommitmentHash::KECCAK256_MASKED160_MSB],
+    pow_hashes: &[PowHash::Keccak256],
+    verifier_friendly_channel_updates: false,
+};
+
+/// The Cairo verifier uses a Poseidon channel and supports Keccak and Blake commitments.
+const CAIRO_VERIFIER_HASHES: VerifierHashes = VerifierHashes {
+    channel_hashes: &[HashFunction::Poseidon3],
+    commitment_hashes: &[
+        CommitmentHash::KECCAK256_MASKED160_LSB,
+        CommitmentHash::KECCAK256_MASKED248_LSB,
+        CommitmentHash::BLAKE256_MASKED160_LSB,
+        CommitmentHash::BLAKE256_MASKED248_LSB,
+    ],
+    pow_hashes: &[PowHash::Keccak256, PowHash::Blake256],
+    verifier_friendly_channel_updates: true,
+};
+
+/// Maximum number of proof of work bits accepted by the Cairo verifier.
+const CAIRO_MAX_PROOF_OF_WORK_BITS: u32 = 50;
+/// Minimum conjectured security accepted by the Cairo verifier, in bits.
+const CAIRO_MIN_SECURITY_BITS: u32 = 50;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ProverParametersError {
+    #[error("the number of steps must be a power of two, got {0}")]
+    NStepsNotPowerOfTwo(u32),
+    #[error("the trace of {0} steps is too long")]
+    TraceTooLong(u32),
+    #[error("last_layer_degree_bound must be a power of two, got {0}")]
+    LastLayerDegreeBoundNotPowerOfTwo(u32),
+    #[error(
+        "FRI parameters do not match the trace: log₂(trace_length) = {expected}, \
+        log₂(last_layer_degree_bound) + ∑fri_step_list = {actual}"
+    )]
+    FriStepsMismatch { expected: u32, actual: u32 },
+    #[error("fri_step_list must not be empty")]
+    EmptyFriStepList,
+    #[error("n_queries must be positive")]
+    NoQueries,
+    #[error("log_n_cosets must be positive, got {0}")]
+    InvalidLogNCosets(i32),
+    #[error("extension fields are not supported")]
+    UnsupportedExtensionField,
+    #[error("verifier-friendly channel updates require the poseidon3 channel hash, got {0}")]
+    VerifierFriendlyChannelHash(HashFunction),
+    #[error("the first FRI step must be 0 for verifier {verifier}, got {value}")]
+    FirstFriStepNotZero { verifier: Verifier, value: u32 },
+    #[error("FRI step {index} must be at most {max} for verifier {verifier}, got {value}")]
+    FriStepOutOfRange {
+        verifier: Verifier,
+        index: usize,
+        value: u32,
+        max: u32,
+    },
+    #[error("FRI step {index} cannot be 0 for verifier {verifier}")]
+    ZeroFriStep { verifier: Verifier, index: usize },
+    #[error("the last FRI step cannot be 1 for verifier {0}")]
+    LastFriStepIsOne(Verifier),
+    #[error("verifier {verifier} accepts at most {max} FRI layers, got {value}")]
+    TooManyFriLayers {
+        verifier: Verifier,
+        value: usize,
+        max: usize,
+    },
+    #[error("verifier {verifier} accepts a last_layer_degree_bound up to {max}, got {value}")]
+    LastLayerDegreeBoundTooLarge {
+        verifier: Verifier,
+        value: u32,
+        max: u32,
+    },
+    #[error("verifier {verifier} accepts at most {max} proof of work bits, got {value}")]
+    TooManyProofOfWorkBits {
+        verifier: Verifier,
+        value: u32,
+        max: u32,
+    },
+    #[error("verifier {verifier} does not support the {value} channel hash")]
+    UnsupportedChannelHash {
+        verifier: Verifier,
+        value: HashFunction,
+    },
+    #[error("verifier {verifier} does not support the {value} commitment hash")]
+    UnsupportedCommitmentHash {
+        verifier: Verifier,
+        value: CommitmentHash,
+    },
+    #[error("verifier {verifier} does not support the {value} proof of work hash")]
+    UnsupportedPowHash { verifier: Verifier, value: PowHash },
+    #[error("verifier {verifier} requires verifier_friendly_channel_updates = {expected}")]
+    VerifierFriendlyChannelUpdatesMismatch { verifier: Verifier, expected: bool },
+    #[error(
+        "verifier {verifier} requires at least {min} bits of conjectured security, got {value}"
+    )]
+    InsufficientSecurity {
+        verifier: Verifier,
+        value: u32,
+        min: u32,
+    },
+}
+
+/// Validates prover parameters against the rules of the Stone prover.
+///
+/// Checks the field and hash settings, the power-of-two rules and the FRI equation:
+/// # log₂(trace_length) = log₂(last_layer_degree_bound) + ∑fri_step_list
+///
+/// The trace length depends on the layout, see `PublicInput::trace_length`.
+///
+/// Returns all the violations found.
+pub fn validate_prover_parameters(
+    public_input: &PublicInput,
+    parameters: &ProverParameters,
+) -> Result<(), Vec<ProverParametersError>> {
+    let mut errors = vec![];
+
+    if parameters.use_extension_field {
+        errors.push(ProverParametersError::UnsupportedExtensionField);
+    }
+    if let Some(channel_hash) = parameters.channel_hash {
+        if parameters.verifier_friendly_channel_updates && channel_hash != HashFunction::Poseidon3 {
+            errors.push(ProverParametersError::VerifierFriendlyChannelHash(
+                channel_hash,
+            ));
+        }
+    }
+    if parameters.stark.log_n_cosets <= 0 {
+        errors.push(ProverParametersError::InvalidLogNCosets(
+            parameters.stark.log_n_cosets,
+        ));
+    }
+
+    let fri = &parameters.stark.fri;
+    if fri.n_queries == 0 {
+        errors.push(ProverParametersError::NoQueries);
+    }
+    if fri.fri_step_list.is_empty() {
+        errors.push(ProverParametersError::EmptyFriStepList);
+    }
+
+    let n_steps = public_input.n_steps;
+    if !n_steps.is_power_of_two() {
+        errors.push(ProverParametersError::NStepsNotPowerOfTwo(n_steps));
+    }
+    if !fri.last_layer_degree_bound.is_power_of_two() {
+        errors.push(ProverParametersError::LastLayerDegreeBoundNotPowerOfTwo(
+            fri.last_layer_degree_bound,
+        ));
+    }
+
+    // The equation is only meaningful if both sides are integers.
+    match public_input.trace_length() {
+        None => errors.push(ProverParametersError::TraceTooLong(n_steps)),
+        Some(trace_length)
+            if trace_length.is_power_of_two() && fri.last_layer_degree_bound.is_power_of_two() =>
+        {
+            let expected = trace_length.ilog2();
+            let actual =
+                fri.last_layer_degree_bound.ilog2() + fri.fri_step_list.iter().sum::<u32>();
+            if expected != actual {
+                errors.push(ProverParametersError::FriStepsMismatch { expected, actual });
+            }
+        }
+        Some(_) => {}
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_hashes(
+    verifier: &Verifier,
+    parameters: &ProverParameters,
+    supported: &VerifierHashes,
+) -> Vec<ProverParametersError> {
+    let mut errors = vec![];
+
+    let channel_hash = parameters
+        .channel_hash
+        .unwrap_or(STONE_DEFAULT_CHANNEL_HASH);
+    if !supported.channel_hashes.contains(&channel_hash) {
+        errors.push(ProverParametersError::UnsupportedChannelHash {
+            verifier: verifier.clone(),
+            value: channel_hash,
+        });
+    }
+    let commitment_hash = parameters
+        .commitment_hash
+        .unwrap_or(STONE_DEFAULT_COMMITMENT_HASH);
+    if !supported.commitment_hashes.contains(&commitment_hash) {
+        errors.push(ProverParametersError::UnsupportedCommitmentHash {
+            verifier: verifier.clone(),
+            value: commitment_hash,
+        });
+    }
+    let pow_hash = parameters.pow_hash.unwrap_or(STONE_DEFAULT_POW_HASH);
+    if !supported.pow_hashes.contains(&pow_hash) {
+        errors.push(ProverParametersError::UnsupportedPowHash {
+            verifier: verifier.clone(),
+            value: pow_hash,
+        });
+    }
+    if parameters.verifier_friendly_channel_updates != supported.verifier_friendly_channel_updates {
+        errors.push(
+            ProverParametersError::VerifierFriendlyChannelUpdatesMismatch {
+                verifier: verifier.clone(),
+                expected: supported.verifier_friendly_channel_updates,
+            },
+        );
+    }
+    errors
+}
+
+fn validate_first_fri_step_is_zero(
+    verifier: Verifier,
+    fri_step_list: &[u32],
+) -> Option<ProverParametersError> {
+    match fri_step_list.first() {
+        Some(&first) if first != 0 => Some(ProverParametersError::FirstFriStepNotZero {
+            verifier,
+            value: first,
+        }),
+        _ => None,
+    }
+}
+
+/// The L1 verifier accepts FRI steps in [0, 1, 2], the first step must be 0 and the last
+/// step cannot be 1. It only supports the default hashes of Stone.
+fn validate_l1_verifier_constraints(parameters: &ProverParameters) -> Vec<ProverParametersError> {
+    let verifier = Verifier::L1;
+    let max_step = FriStepConstraints::for_verifier(&verifier).max_step;
+    let fri_step_list = &parameters.stark.fri.fri_step_list;
+    let mut errors: Vec<_> = validate_first_fri_step_is_zero(verifier.clone(), fri_step_list)
+        .into_iter()
+        .collect();
+
+    for (index, &value) in fri_step_list.iter().enumerate() {
+        if value > max_step {
+            errors.push(ProverParametersError::FriStepOutOfRange {
+                verifier: verifier.clone(),
+                index,
+                value,
+                max: max_step,
+            });
+        }
+    }
+    if fri_step_list.len() > 1 && fri_step_list.last() == Some(&1) {
+        errors.push(ProverParametersError::LastFriStepIsOne(verifier.clone()));
+    }
+    errors.extend(validate_hashes(&verifier, parameters, &L1_VERIFIER_HASHES));
+    errors
+}
+
+/// The Cairo verifier requires the first FRI step to be 0 and the other steps to be
+/// in [1, 4]. It also bounds the number of layers, the last layer degree bound and
+/// the proof of work, and requires a minimum security level and a Poseidon channel.
+fn validate_cairo_verifier_constraints(
+    parameters: &ProverParameters,
+) -> Vec<ProverParametersError> {
+    let verifier = Verifier::Cairo;
+    let constraints = FriStepConstraints::for_verifier(&verifier);
+    let max_step = constraints.max_step;
+    let fri = &parameters.stark.fri;
+    let mut errors: Vec<_> = validate_first_fri_step_is_zero(verifier.clone(), &fri.fri_step_list)
+        .into_iter()
+        .collect();
+
+    for (index, &value) in fri.fri_step_list.iter().enumerate().skip(1) {
+        if value == 0 {
+            errors.push(ProverParametersError::ZeroFriStep {
+                verifier: verifier.clone(),
+                index,
+            });
+        } else if value > max_step {
+            errors.push(ProverParametersError::FriStepOutOfRange {
+                verifier: verifier.clone(),
+                index,
+                value,
+                max: max_step,
+            });
+        }
+    }
+    if let Some(max_layers) = constraints.max_layers {
+        if fri.fri_step_list.len() > max_layers {
+            errors.push(ProverParametersError::TooManyFriLayers {
+                verifier: verifier.clone(),
+                value: fri.fri_step_list.len(),
+                max: max_layers,
+            });
+        }
+    }
+    if let Some(max_log_degree_bound) = constraints.max_last_layer_log_degree_bound {
+        let max_last_layer_degree_bound = 1 << max_log_degree_bound;
+        if fri.last_layer_degree_bound > max_last_layer_degree_bound {
+            errors.push(ProverParametersError::LastLayerDegreeBoundTooLarge {
+                verifier: verifier.clone(),
+                value: fri.last_layer_degree_bound,
+                max: max_last_layer_degree_bound,
+            });
+        }
+    }
+    if fri.proof_of_work_bits > CAIRO_MAX_PROOF_OF_WORK_BITS {
+        errors.push(ProverParametersError::TooManyProofOfWorkBits {
+            verifier: verifier.clone(),
+            value: fri.proof_of_work_bits,
+            max: CAIRO_MAX_PROOF_OF_WORK_BITS,
+        });
+    }
+    // An invalid log_n_cosets is reported by `validate_prover_parameters`.
+    let log_n_cosets = parameters.stark.log_n_cosets.max(0) as u32;
+    let security_bits = conjectured_security_bits(fri, log_n_cosets);
+    if security_bits < CAIRO_MIN_SECURITY_BITS {
+        errors.push(ProverParametersError::InsufficientSecurity {
+            verifier: verifier.clone(),
+            value: security_bits,
+            min: CAIRO_MIN_SECURITY_BITS,
+        });
+    }
+    errors.extend(validate_hashes(
+        &verifier,
+        parameters,
+        &CAIRO_VERIFIER_HASHES,
+    ));
+    errors
+}
+
+/// Validates prover parameters against the rules of the Stone prover and the constraints
+/// of the verifier that will check the proof.
+///
+/// Returns all the violations found.
+pub fn validate_prover_parameters_for_verifier(
+    public_input: &PublicInput,
+    parameters: &ProverParameters,
+    verifier: &Verifier,
+) -> Result<(), Vec<ProverParametersError>> {
+    let mut errors = validate_prover_parameters(public_input, parameters)
+        .err()
+        .unwrap_or_default();
+
+    match verifier {
+        Verifier::Stone => {}
+        Verifier::L1 => errors.extend(validate_l1_verifier_constraints(parameters)),
+        Verifier::Cairo => errors.extend(validate_cairo_verifier_constraints(parameters)),
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use crate::fri::generate_prover_parameters;
+    use crate::models::Layout;
+
+    use super::*;
+
+    fn public_input(n_steps: u32) -> PublicInput {
+        PublicInput {
+            layout: Layout::Small,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: None,
+        }
+    }
+
+    #[rstest]
+    #[case(Verifier::Stone, 32768)]
+    #[case(Verifier::Stone, 768)]
+    #[case(Verifier::L1, 32768)]
+    #[case(Verifier::L1, 524288)]
+    #[case(Verifier::Cairo, 32768)]
+    #[case(Verifier::Cairo, 768)]
+    fn test_generated_parameters_are_valid(#[case] verifier: Verifier, #[case] n_steps: u32) {
+        let n_steps = n_steps.next_power_of_two();
+        let parameters = generate_prover_parameters(n_steps, verifier.clone());
+        validate_prover_parameters_for_verifier(&public_input(n_steps), &parameters, &verifier)
+            .unwrap();
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_reports_all_errors() {
+        let mut parameters = generate_prover_parameters(32768, Verifier::Stone);
+        parameters.use_extension_field = true;
+        parameters.verifier_friendly_channel_updates = true;
+        parameters.channel_hash = Some(HashFunction::Keccak256);
+        parameters.stark.log_n_cosets = 0;
+        parameters.stark.fri.n_queries = 0;
+        parameters.stark.fri.last_layer_degree_bound = 32;
+
+        let errors = validate_prover_parameters(&public_input(32768), &parameters).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ProverParametersError::UnsupportedExtensionField,
+                ProverParametersError::VerifierFriendlyChannelHash(HashFunction::Keccak256),
+                ProverParametersError::InvalidLogNCosets(0),
+                ProverParametersError::NoQueries,
+                ProverParametersError::FriStepsMismatch {
+                    expected: 19,
+                    actual: 18
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_power_of_two() {
+        let mut parameters = generate_prover_parameters(32768, Verifier::Stone);
+        parameters.stark.fri.last_layer_degree_bound = 48;
+
+        let errors = validate_prover_parameters(&public_input(1000), &parameters).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ProverParametersError::NStepsNotPowerOfTwo(1000),
+                ProverParametersError::LastLayerDegreeBoundNotPowerOfTwo(48),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_trace_too_long() {
+        let parameters = generate_prover_parameters(32768, Verifier::Stone);
+
+        let errors = validate_prover_parameters(&public_input(1 << 28), &parameters).unwrap_err();
+        assert_eq!(errors, vec![ProverParametersError::TraceTooLong(1 << 28)]);
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_for_l1_verifier() {
+        // Valid for the Stone verifier, but not for the L1 verifier.
+        let parameters = generate_prover_parameters(32768, Verifier::Stone);
+        validate_prover_parameters_for_verifier(
+            &public_input(32768),
+            &parameters,
+            &Verifier::Stone,
+        )
+        .unwrap();
+
+        let errors = validate_prover_parameters_for_verifier(
+            &public_input(32768),
+            &parameters,
+            &Verifier::L1,
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ProverParametersError::FirstFriStepNotZero {
+                    verifier: Verifier::L1,
+                    value: 4
+                },
+                ProverParametersError::FriStepOutOfRange {
+                    verifier: Verifier::L1,
+                    index: 0,
+                    value: 4,
+                    max: 2
+                },
+                ProverParametersError::FriStepOutOfRange {
+                    verifier: Verifier::L1,
+                    index: 1,
+                    value: 4,
+                    max: 2
+                },
+                ProverParametersError::FriStepOutOfRange {
+                    verifier: Verifier::L1,
+                    index: 2,
+                    value: 4,
+                    max: 2
+                },
+                ProverParametersError::LastFriStepIsOne(Verifier::L1),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_for_cairo_verifier() {
+        let mut parameters = generate_prover_parameters(32768, Verifier::Cairo);
+        parameters.stark.fri.fri_step_list = vec![0, 5, 0, 4, 4];
+        parameters.stark.fri.proof_of_work_bits = 51;
+
+        let errors = validate_prover_parameters_for_verifier(
+            &public_input(32768),
+            &parameters,
+            &Verifier::Cairo,
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ProverParametersError::FriStepOutOfRange {
+                    verifier: Verifier::Cairo,
+                    index: 1,
+                    value: 5,
+                    max: 4
+                },
+                ProverParametersError::ZeroFriStep {
+                    verifier: Verifier::Cairo,
+                    index: 2
+                },
+                ProverParametersError::TooManyProofOfWorkBits {
+                    verifier: Verifier::Cairo,
+                    value: 51,
+                    max: 50
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_hashes() {
+        // Valid FRI parameters for both verifiers, only the hashes differ.
+        let keccak_parameters = generate_prover_parameters(32768, Verifier::L1);
+        let mut poseidon_parameters = keccak_parameters.clone();
+        poseidon_parameters.channel_hash = Some(HashFunction::Poseidon3);
+        poseidon_parameters.commitment_hash = Some(CommitmentHash::KECCAK256_MASKED160_LSB);
+        poseidon_parameters.pow_hash = Some(PowHash::Keccak256);
+        poseidon_parameters.verifier_friendly_channel_updates = true;
+        let validate = |parameters: &ProverParameters, verifier: Verifier| {
+            validate_prover_parameters_for_verifier(&public_input(32768), parameters, &verifier)
+        };
+
+        validate(&keccak_parameters, Verifier::L1).unwrap();
+        validate(&poseidon_parameters, Verifier::Cairo).unwrap();
+        assert_eq!(
+            validate(&keccak_parameters, Verifier::Cairo).unwrap_err(),
+            vec![
+                ProverParametersError::UnsupportedChannelHash {
+                    verifier: Verifier::Cairo,
+                    value: HashFunction::Keccak256
+                },
+                ProverParametersError::UnsupportedCommitmentHash {
+                    verifier: Verifier::Cairo,
+                    value: CommitmentHash::KECCAK256_MASKED160_MSB
+                },
+                ProverParametersError::VerifierFriendlyChannelUpdatesMismatch {
+                    verifier: Verifier::Cairo,
+                    expected: true
+                },
+            ]
+        );
+        assert_eq!(
+            validate(&poseidon_parameters, Verifier::L1).unwrap_err(),
+            vec![
+                ProverParametersError::UnsupportedChannelHash {
+                    verifier: Verifier::L1,
+                    value: HashFunction::Poseidon3
+                },
+                ProverParametersError::UnsupportedCommitmentHash {
+                    verifier: Verifier::L1,
+                    value: CommitmentHash::KECCAK256_MASKED160_LSB
+                },
+                ProverParametersError::VerifierFriendlyChannelUpdatesMismatch {
+                    verifier: Verifier::L1,
+                    expected: false
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_validate_prover_parameters_for_cairo_verifier_security() {
+        let mut parameters = generate_prover_parameters(32768, Verifier::Cairo);
+        parameters.stark.fri.n_queries = 4;
+        parameters.stark.fri.proof_of_work_bits = 20;
+
+        let errors = validate_prover_parameters_for_verifier(
+            &public_input(32768),
+            &parameters,
+            &Verifier::Cairo,
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![ProverParametersError::InsufficientSecurity {
+                verifier: Verifier::Cairo,
+                value: 36,
+                min: 50
+            }]
+        );
+
+        // The same parameters are accepted by the Stone verifier.
+        validate_prover_parameters_for_verifier(
+            &public_input(32768),
+            &parameters,
+            &Verifier::Stone,
+        )
+        .unwrap();
+    }
+
+    #[rstest]
+    #[case(
+        ProverParametersError::LastFriStepIsOne(Verifier::L1),
+        "the last FRI step cannot be 1 for verifier l1"
+    )]
+    #[case(
+        ProverParametersError::UnsupportedPowHash {
+            verifier: Verifier::Cairo,
+            value: PowHash::Blake256
+        },
+        "verifier cairo does not support the blake256 proof of work hash"
+    )]
+    fn test_error_messages(#[case] error: ProverParametersError, #[case] expected: &str) {
+        assert_eq!(error.to_string(), expected);
+    }
+}
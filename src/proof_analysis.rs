@@ -0,0 +1,105 @@
+//! Analysis of existing proofs.
+
+use crate::fri::conjectured_security_bits;
+use crate::models::{Layout, Proof, ProverParameters, Verifier};
+use crate::params_validation::validate_prover_parameters_for_verifier;
+
+/// Summary of the parameters and properties of a proof.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofAnalysis {
+    /// Parameters the proof was generated with.
+    pub proof_parameters: ProverParameters,
+    pub layout: Layout,
+    /// Conjectured security of the proof, in bits.
+    pub security_bits: u32,
+    /// Number of rows of the execution trace, `None` if the public input declares a trace
+    /// too long to be proven.
+    pub trace_length: Option<u32>,
+    /// Number of FRI layers, one per FRI step.
+    pub n_fri_layers: usize,
+    /// Size of the proof, in bytes.
+    pub proof_size: usize,
+    /// Verifiers that accept proofs with these layout and parameters.
+    pub verifiers: Vec<Verifier>,
+}
+
+/// Infers the security and configuration of a proof from its content.
+///
+/// * `proof`: the proof to analyze.
+pub fn analyze_proof(proof: &Proof) -> ProofAnalysis {
+    let public_input = &proof.public_input;
+    let parameters = &proof.proof_parameters;
+    let fri = &parameters.stark.fri;
+
+    let verifiers = public_input
+        .layout
+        .params()
+        .verifiers
+        .iter()
+        .filter(|verifier| {
+            validate_prover_parameters_for_verifier(public_input, parameters, verifier).is_ok()
+        })
+        .cloned()
+        .collect();
+
+    ProofAnalysis {
+        proof_parameters: parameters.clone(),
+        layout: public_input.layout.clone(),
+        security_bits: conjectured_security_bits(fri, parameters.stark.log_n_cosets.max(0) as u32),
+        trace_length: public_input.trace_length(),
+        n_fri_layers: fri.fri_step_list.len(),
+        proof_size: proof.proof_hex.trim_start_matches("0x").len() / 2,
+        verifiers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::fri::generate_prover_parameters_for_public_input;
+    use crate::test_utils::{parsed_prover_test_case, ParsedProverTestCase};
+
+    use super::*;
+
+    #[rstest]
+    fn test_analyze_proof(parsed_prover_test_case: ParsedProverTestCase) {
+        let proof = parsed_prover_test_case.proof;
+
+        let analysis = analyze_proof(&proof);
+
+        // The fibonacci fixture runs 32768 steps with the starknet_with_keccak layout,
+        // 18 queries with a blowup factor of 16 and 24 bits of proof of work.
+        assert_eq!(analysis.proof_parameters, proof.proof_parameters);
+        assert_eq!(analysis.layout, Layout::StarknetWithKeccak);
+        assert_eq!(analysis.security_bits, 96);
+        assert_eq!(analysis.trace_length, Some(524288));
+        assert_eq!(analysis.n_fri_layers, 5);
+        // The proof uses the default Keccak channel of Stone and FRI steps of 4, which only
+        // the Stone verifier accepts.
+        assert_eq!(analysis.verifiers, vec![Verifier::Stone]);
+    }
+
+    #[rstest]
+    fn test_analyze_proof_excludes_rejecting_verifiers(
+        parsed_prover_test_case: ParsedProverTestCase,
+    ) {
+        let mut proof = parsed_prover_test_case.proof;
+        proof.proof_parameters =
+            generate_prover_parameters_for_public_input(&proof.public_input, Verifier::Cairo)
+                .unwrap();
+        let analysis = analyze_proof(&proof);
+        assert!(analysis.verifiers.contains(&Verifier::Stone));
+        assert!(analysis.verifiers.contains(&Verifier::Cairo));
+
+        // The Cairo verifier accepts FRI steps up to 4.
+        let fri = &mut proof.proof_parameters.stark.fri;
+        let total: u32 = fri.fri_step_list.iter().sum();
+        fri.fri_step_list = vec![0, total];
+
+        let analysis = analyze_proof(&proof);
+
+        assert!(analysis.verifiers.contains(&Verifier::Stone));
+        assert!(!analysis.verifiers.contains(&Verifier::Cairo));
+    }
+}
@@ -0,0 +1,420 @@
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::json::{read_json_from_file, write_json_to_file};
+use crate::models::{Artifact, Proof, ProverConfig, ProverParameters, PublicInput};
+use crate::prover::ArtifactRef;
+
+/// Key of a proof in a `ProofCache`: the SHA-256 hash of the prover inputs, as hex.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProofCacheKey(String);
+
+impl ProofCacheKey {
+    /// Computes the cache key of a prover job.
+    ///
+    /// * `public_input`: the public prover input generated by the Cairo program.
+    /// * `memory`: the memory output of the Cairo program.
+    /// * `trace`: the execution trace of the Cairo program.
+    /// * `prover_config`: prover configuration.
+    /// * `parameters`: prover parameters for the Cairo program.
+    pub fn new(
+        public_input: &PublicInput,
+        memory: &Artifact,
+        trace: &Artifact,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+    ) -> Result<Self, std::io::Error> {
+        Self::from_artifact_refs(
+            public_input,
+            memory.into(),
+            trace.into(),
+            prover_config,
+            parameters,
+        )
+    }
+
+    pub(crate) fn from_artifact_refs(
+        public_input: &PublicInput,
+        memory: ArtifactRef<'_>,
+        trace: ArtifactRef<'_>,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+    ) -> Result<Self, std::io::Error> {
+        let mut hasher = Sha256::new();
+        hash_json(&mut hasher, public_input)?;
+        hash_artifact(&mut hasher, memory)?;
+        hash_artifact(&mut hasher, trace)?;
+        hash_json(&mut hasher, prover_config)?;
+        hash_json(&mut hasher, parameters)?;
+
+        Ok(Self(format!("{:x}", hasher.finalize())))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Sorts the keys of the JSON objects in `value`, recursively.
+///
+/// The inputs contain hash maps, which are serialized in a different order in each process.
+fn sort_json_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_json_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_json_keys).collect()),
+        value => value,
+    }
+}
+
+/// Hashes a value as canonical JSON, prefixed with its length to separate it from the next
+/// input.
+fn hash_json<T: Serialize>(hasher: &mut Sha256, value: &T) -> Result<(), std::io::Error> {
+    let bytes = serde_json::to_vec(&sort_json_keys(serde_json::to_value(value)?))?;
+    hasher.update((bytes.len() as u64).to_le_bytes());
+    hasher.update(&bytes);
+    Ok(())
+}
+
+fn hash_artifact(hasher: &mut Sha256, artifact: ArtifactRef<'_>) -> Result<(), std::io::Error> {
+    match artifact {
+        ArtifactRef::InMemory(content) => {
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(content);
+        }
+        ArtifactRef::OnDisk(path) => {
+            let file = File::open(path)?;
+            hasher.update(file.metadata()?.len().to_le_bytes());
+            let mut reader = BufReader::new(file);
+            let mut buffer = [0u8; 64 * 1024];
+            loop {
+                let n_bytes = reader.read(&mut buffer)?;
+                if n_bytes == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..n_bytes]);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum ProofCacheError {
+    #[error("failed to access the proof cache")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Hit and miss counters of a proof cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub insertions: u64,
+    pub evictions: u64,
+}
+
+/// Storage for proofs, indexed by the hash of their inputs.
+pub trait ProofCache: Send + Sync {
+    /// Returns the proof stored for `key`, if any.
+    fn get(&self, key: &ProofCacheKey) -> Result<Option<Proof>, ProofCacheError>;
+
+    /// Stores a proof. May evict other proofs, depending on the eviction policy.
+    fn put(&self, key: &ProofCacheKey, proof: &Proof) -> Result<(), ProofCacheError>;
+
+    /// Returns the statistics of the cache since its creation.
+    fn stats(&self) -> ProofCacheStats;
+}
+
+static DEFAULT_PROOF_CACHE: RwLock<Option<Arc<dyn ProofCache>>> = RwLock::new(None);
+
+/// Sets the cache consulted by `prover::run_prover` and `prover::run_prover_with_artifacts`.
+///
+/// No cache is used by default. Pass `None` to disable the cache.
+///
+/// * `cache`: proof cache shared by all the threads of the process.
+pub fn set_default_proof_cache(cache: Option<Arc<dyn ProofCache>>) {
+    *DEFAULT_PROOF_CACHE.write().unwrap() = cache;
+}
+
+/// Returns the cache set with `set_default_proof_cache`, if any.
+pub fn default_proof_cache() -> Option<Arc<dyn ProofCache>> {
+    DEFAULT_PROOF_CACHE.read().unwrap().clone()
+}
+
+/// Determines which proofs are removed from a cache when it grows.
+///
+/// Proofs are evicted from the least recently used to the most recently used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum EvictionPolicy {
+    /// Never evict proofs.
+    #[default]
+    Never,
+    /// Keep at most the specified number of proofs.
+    MaxEntries(usize),
+    /// Keep the total size of the proofs under the specified number of bytes.
+    MaxSize(u64),
+    /// Evict proofs that were not used for the specified duration.
+    MaxAge(Duration),
+}
+
+#[derive(Debug, Default)]
+struct AtomicStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    insertions: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// Proof cache storing each proof as a JSON file in a directory.
+///
+/// The modification time of the files is used to track when a proof was last used.
+#[derive(Debug)]
+pub struct FsProofCache {
+    dir: PathBuf,
+    eviction_policy: EvictionPolicy,
+    stats: AtomicStats,
+}
+
+/// Proof file in the cache, used for eviction.
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+impl FsProofCache {
+    /// Opens a cache in `dir`, created if needed.
+    ///
+    /// * `dir`: Directory where the proofs are stored.
+    /// * `eviction_policy`: Policy applied when a proof is added to the cache.
+    pub fn new(dir: PathBuf, eviction_policy: EvictionPolicy) -> Result<Self, ProofCacheError> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            eviction_policy,
+            stats: AtomicStats::default(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn proof_file(&self, key: &ProofCacheKey) -> PathBuf {
+        self.dir.join(format!("{}.json", key.as_str()))
+    }
+
+    fn list_entries(&self) -> Result<Vec<CacheEntry>, std::io::Error> {
+        let mut entries = vec![];
+        for dir_entry in std::fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let metadata = std::fs::metadata(&path)?;
+            entries.push(CacheEntry {
+                path,
+                size: metadata.len(),
+                last_used: metadata.modified()?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Removes proofs according to the eviction policy. The proof at `keep`, which was just
+    /// inserted, is considered as the most recently used and is never removed.
+    fn evict(&self, keep: &Path) -> Result<(), std::io::Error> {
+        if self.eviction_policy == EvictionPolicy::Never {
+            return Ok(());
+        }
+
+        // Sort from the most recently used to the least recently used
+        let mut entries = self.list_entries()?;
+        entries.sort_by_key(|entry| (entry.path != keep, Reverse(entry.last_used)));
+
+        let now = SystemTime::now();
+        let mut total_size = 0;
+        for (index, entry) in entries.into_iter().enumerate() {
+            total_size += entry.size;
+            let evict = match self.eviction_policy {
+                EvictionPolicy::Never => false,
+                EvictionPolicy::MaxEntries(max_entries) => index >= max_entries,
+                EvictionPolicy::MaxSize(max_size) => total_size > max_size,
+                EvictionPolicy::MaxAge(max_age) => now
+                    .duration_since(entry.last_used)
+                    .is_ok_and(|age| age > max_age),
+            };
+            if evict && entry.path != keep {
+                std::fs::remove_file(&entry.path)?;
+                self.stats.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProofCache for FsProofCache {
+    fn get(&self, key: &ProofCacheKey) -> Result<Option<Proof>, ProofCacheError> {
+        let proof_file = self.proof_file(key);
+        if !proof_file.exists() {
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+
+        let proof = read_json_from_file(&proof_file)?;
+        // Mark the proof as recently used
+        File::options()
+            .write(true)
+            .open(&proof_file)?
+            .set_modified(SystemTime::now())?;
+        self.stats.hits.fetch_add(1, Ordering::Relaxed);
+        Ok(Some(proof))
+    }
+
+    fn put(&self, key: &ProofCacheKey, proof: &Proof) -> Result<(), ProofCacheError> {
+        // Write to a temporary file first to never expose a partially written proof.
+        let proof_file = self.proof_file(key);
+        let tmp_file = proof_file.with_extension("json.tmp");
+        write_json_to_file(proof, &tmp_file)?;
+        std::fs::rename(&tmp_file, &proof_file)?;
+        self.stats.insertions.fetch_add(1, Ordering::Relaxed);
+
+        self.evict(&proof_file)?;
+        Ok(())
+    }
+
+    fn stats(&self) -> ProofCacheStats {
+        ProofCacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            insertions: self.stats.insertions.load(Ordering::Relaxed),
+            evictions: self.stats.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::fri::generate_prover_parameters;
+    use crate::models::{Layout, MemorySegmentAddresses, Verifier};
+    use crate::prover::create_temp_dir;
+    use crate::test_utils::{parsed_prover_test_case, ParsedProverTestCase};
+
+    use super::*;
+
+    fn cache_key(test_case: &ParsedProverTestCase, trace: &Artifact) -> ProofCacheKey {
+        ProofCacheKey::new(
+            &test_case.public_input,
+            &Artifact::InMemory(test_case.memory.clone()),
+            trace,
+            &test_case.prover_config,
+            &test_case.prover_parameters,
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    fn test_cache_key(parsed_prover_test_case: ParsedProverTestCase) {
+        let trace = &parsed_prover_test_case.trace;
+        let key = cache_key(&parsed_prover_test_case, &Artifact::InMemory(trace.clone()));
+        assert_eq!(key.as_str().len(), 64);
+
+        // The key does not depend on the location of the artifacts
+        let dir = create_temp_dir().unwrap();
+        let trace_file = dir.join("trace.bin");
+        std::fs::write(&trace_file, trace).unwrap();
+        let on_disk_key = cache_key(&parsed_prover_test_case, &Artifact::OnDisk(trace_file));
+        assert_eq!(key, on_disk_key);
+
+        let other_key = cache_key(&parsed_prover_test_case, &Artifact::InMemory(vec![0]));
+        assert_ne!(key, other_key);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Check that the key does not depend on the iteration order of the hash maps
+    /// of the inputs.
+    #[rstest]
+    fn test_cache_key_is_canonical() {
+        let public_input = PublicInput {
+            layout: Layout::Starknet,
+            rc_min: 0,
+            rc_max: 100,
+            n_steps: 1 << 10,
+            memory_segments: (0..16)
+                .map(|i| {
+                    let segment = MemorySegmentAddresses {
+                        begin_addr: i,
+                        stop_ptr: i + 1,
+                    };
+                    (format!("segment_{i}"), segment)
+                })
+                .collect(),
+            public_memory: vec![],
+            dynamic_params: None,
+        };
+        let parameters = generate_prover_parameters(public_input.n_steps, Verifier::Stone);
+        let key = |public_input: &PublicInput| {
+            ProofCacheKey::new(
+                public_input,
+                &Artifact::InMemory(vec![1]),
+                &Artifact::InMemory(vec![2]),
+                &ProverConfig::default(),
+                &parameters,
+            )
+            .unwrap()
+        };
+
+        let public_input_copy: PublicInput =
+            serde_json::from_str(&serde_json::to_string(&public_input).unwrap()).unwrap();
+        assert_eq!(key(&public_input), key(&public_input_copy));
+    }
+
+    #[rstest]
+    fn test_fs_proof_cache(parsed_prover_test_case: ParsedProverTestCase) {
+        let dir = create_temp_dir().unwrap();
+        let cache = FsProofCache::new(dir.clone(), EvictionPolicy::MaxEntries(1)).unwrap();
+        let key = ProofCacheKey("a".repeat(64));
+        let other_key = ProofCacheKey("b".repeat(64));
+
+        assert!(cache.get(&key).unwrap().is_none());
+        cache.put(&key, &parsed_prover_test_case.proof).unwrap();
+        let proof = cache.get(&key).unwrap().unwrap();
+        assert_eq!(proof.proof_hex, parsed_prover_test_case.proof.proof_hex);
+
+        // Adding a second proof evicts the first one
+        cache.put(&other_key, &proof).unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+
+        assert_eq!(
+            cache.stats(),
+            ProofCacheStats {
+                hits: 1,
+                misses: 2,
+                insertions: 2,
+                evictions: 1,
+            }
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,382 @@
+//! Compact binary encoding of proofs.
+//!
+//! Stone JSON proofs are dominated by `proof_hex` and the private input. The compact encoding
+//! stores the proof as raw bytes instead of hex, and the rest of the proof as compact JSON,
+//! in a bincode payload optionally compressed with zstd (requires the `zstd` feature).
+//! The private input is kept as JSON: its repeated keys and paths are only shrunk by zstd.
+//! The other fields are kept as JSON as well: they skip default values when serialized,
+//! which bincode cannot decode as it does not store field names.
+//!
+//! Decoding a compact proof gives back the encoded `Proof`, which serializes to the same
+//! Stone JSON. Fields of a proof file unknown to `Proof` are dropped when the file is parsed,
+//! before encoding, so they cannot be restored.
+//!
+//! Format: `COMPACT_PROOF_MAGIC`, the format version (1 byte), the compression (1 byte),
+//! then the payload.
+
+use bincode::{Decode, Encode};
+use cairo_vm::air_private_input::AirPrivateInputSerializable;
+use serde::{Deserialize, Serialize};
+use stark_evm_adapter::annotation_parser::SplitProofs;
+
+use crate::models::{Proof, ProverConfig, ProverParameters, PublicInput};
+
+/// First bytes of a compact proof.
+pub const COMPACT_PROOF_MAGIC: &[u8; 4] = b"STPF";
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = COMPACT_PROOF_MAGIC.len() + 2;
+/// Maximum size of a payload, once decompressed. Bounds the memory used to decode
+/// untrusted compact proofs.
+pub const MAX_PAYLOAD_LEN: usize = 256 << 20;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZSTD: u8 = 1;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProofEncodingError {
+    #[error("not a compact proof")]
+    InvalidMagic,
+    #[error("unsupported compact proof version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("unsupported compression: {0}, is the zstd feature enabled?")]
+    UnsupportedCompression(u8),
+    #[error("the payload of the compact proof exceeds {} bytes", MAX_PAYLOAD_LEN)]
+    PayloadTooLarge,
+    #[error(transparent)]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error(transparent)]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Compression of the payload of a compact proof.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    /// zstd compression with the given level, from 1 to 22. Requires the `zstd` feature.
+    Zstd(i32),
+}
+
+impl Compression {
+    fn tag(&self) -> u8 {
+        match self {
+            Self::None => COMPRESSION_NONE,
+            Self::Zstd(_) => COMPRESSION_ZSTD,
+        }
+    }
+}
+
+/// Configuration of bincode, limiting the memory allocated while decoding.
+fn bincode_config() -> impl bincode::config::Config {
+    bincode::config::standard().with_limit::<MAX_PAYLOAD_LEN>()
+}
+
+/// Proof bytes, as found in `proof_hex`.
+#[derive(Encode, Decode, Debug, PartialEq)]
+enum ProofBytes {
+    /// Decoded lowercase hex string, with or without the `0x` prefix.
+    Hex { prefixed: bool, bytes: Vec<u8> },
+    /// Hex strings that cannot be restored from their bytes, stored as is.
+    Raw(String),
+}
+
+impl ProofBytes {
+    fn from_hex(proof_hex: &str) -> Self {
+        let (prefixed, digits) = match proof_hex.strip_prefix("0x") {
+            Some(digits) => (true, digits),
+            None => (false, proof_hex),
+        };
+        match decode_lowercase_hex(digits) {
+            Some(bytes) => Self::Hex { prefixed, bytes },
+            None => Self::Raw(proof_hex.to_string()),
+        }
+    }
+
+    fn into_hex(self) -> String {
+        match self {
+            Self::Hex { prefixed, bytes } => {
+                let prefix = if prefixed { "0x" } else { "" };
+                let mut proof_hex = String::with_capacity(prefix.len() + 2 * bytes.len());
+                proof_hex.push_str(prefix);
+                for byte in bytes {
+                    proof_hex.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+                    proof_hex.push(HEX_DIGITS[(byte & 0xf) as usize] as char);
+                }
+                proof_hex
+            }
+            Self::Raw(proof_hex) => proof_hex,
+        }
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Decodes a lowercase hex string. Returns `None` for any other string, as the bytes
+/// would not give back the same string.
+fn decode_lowercase_hex(digits: &str) -> Option<Vec<u8>> {
+    fn digit_value(digit: u8) -> Option<u8> {
+        match digit {
+            b'0'..=b'9' => Some(digit - b'0'),
+            b'a'..=b'f' => Some(digit - b'a' + 10),
+            _ => None,
+        }
+    }
+
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .as_bytes()
+        .chunks_exact(2)
+        .map(|pair| Some(digit_value(pair[0])? << 4 | digit_value(pair[1])?))
+        .collect()
+}
+
+/// Payload of a compact proof.
+#[derive(Encode, Decode, Debug, PartialEq)]
+struct CompactProof {
+    proof: ProofBytes,
+    /// JSON encoding of the other fields of the proof.
+    metadata: Vec<u8>,
+}
+
+#[derive(Serialize)]
+struct ProofMetadataRef<'a> {
+    private_input: &'a AirPrivateInputSerializable,
+    proof_parameters: &'a ProverParameters,
+    prover_config: &'a ProverConfig,
+    public_input: &'a PublicInput,
+    split_proofs: &'a Option<SplitProofs>,
+}
+
+#[derive(Deserialize)]
+struct ProofMetadata {
+    private_input: AirPrivateInputSerializable,
+    proof_parameters: ProverParameters,
+    prover_config: ProverConfig,
+    public_input: PublicInput,
+    split_proofs: Option<SplitProofs>,
+}
+
+/// Encodes a proof in the compact binary format.
+///
+/// * `proof`: the proof to encode.
+/// * `compression`: compression of the payload.
+pub fn encode_proof(
+    proof: &Proof,
+    compression: Compression,
+) -> Result<Vec<u8>, ProofEncodingError> {
+    let metadata = ProofMetadataRef {
+        private_input: &proof.private_input,
+        proof_parameters: &proof.proof_parameters,
+        prover_config: &proof.prover_config,
+        public_input: &proof.public_input,
+        split_proofs: &proof.split_proofs,
+    };
+    let compact_proof = CompactProof {
+        proof: ProofBytes::from_hex(&proof.proof_hex),
+        metadata: serde_json::to_vec(&metadata)?,
+    };
+    let payload = bincode::encode_to_vec(compact_proof, bincode_config())?;
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(ProofEncodingError::PayloadTooLarge);
+    }
+
+    let mut encoded_proof = Vec::with_capacity(HEADER_LEN + payload.len());
+    encoded_proof.extend_from_slice(COMPACT_PROOF_MAGIC);
+    encoded_proof.push(FORMAT_VERSION);
+    encoded_proof.push(compression.tag());
+    match compression {
+        Compression::None => encoded_proof.extend_from_slice(&payload),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(level) => {
+            encoded_proof.extend_from_slice(&zstd::encode_all(payload.as_slice(), level)?)
+        }
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd(_) => {
+            return Err(ProofEncodingError::UnsupportedCompression(
+                compression.tag(),
+            ))
+        }
+    }
+
+    Ok(encoded_proof)
+}
+
+/// Decodes a proof encoded with `encode_proof`.
+///
+/// Payloads larger than `MAX_PAYLOAD_LEN` once decompressed are rejected.
+///
+/// * `encoded_proof`: the compact proof.
+pub fn decode_proof(encoded_proof: &[u8]) -> Result<Proof, ProofEncodingError> {
+    if encoded_proof.len() < HEADER_LEN || !encoded_proof.starts_with(COMPACT_PROOF_MAGIC) {
+        return Err(ProofEncodingError::InvalidMagic);
+    }
+    let version = encoded_proof[COMPACT_PROOF_MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(ProofEncodingError::UnsupportedVersion(version));
+    }
+    let compression = encoded_proof[COMPACT_PROOF_MAGIC.len() + 1];
+    let payload = &encoded_proof[HEADER_LEN..];
+
+    #[cfg(feature = "zstd")]
+    let decompressed_payload;
+    let payload = match compression {
+        COMPRESSION_NONE => payload,
+        #[cfg(feature = "zstd")]
+        COMPRESSION_ZSTD => {
+            decompressed_payload = decompress_payload(payload)?;
+            decompressed_payload.as_slice()
+        }
+        other => return Err(ProofEncodingError::UnsupportedCompression(other)),
+    };
+
+    let (compact_proof, _): (CompactProof, usize) =
+        bincode::decode_from_slice(payload, bincode_config())?;
+    let metadata: ProofMetadata = serde_json::from_slice(&compact_proof.metadata)?;
+
+    Ok(Proof {
+        private_input: metadata.private_input,
+        proof_hex: compact_proof.proof.into_hex(),
+        proof_parameters: metadata.proof_parameters,
+        prover_config: metadata.prover_config,
+        public_input: metadata.public_input,
+        split_proofs: metadata.split_proofs,
+    })
+}
+
+/// Decompresses a zstd payload, stopping as soon as it exceeds `MAX_PAYLOAD_LEN`.
+#[cfg(feature = "zstd")]
+fn decompress_payload(payload: &[u8]) -> Result<Vec<u8>, ProofEncodingError> {
+    use std::io::Read;
+
+    let mut decompressed_payload = vec![];
+    zstd::stream::read::Decoder::new(payload)?
+        .take(MAX_PAYLOAD_LEN as u64 + 1)
+        .read_to_end(&mut decompressed_payload)?;
+    if decompressed_payload.len() > MAX_PAYLOAD_LEN {
+        return Err(ProofEncodingError::PayloadTooLarge);
+    }
+    Ok(decompressed_payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::test_utils::{parsed_prover_test_case, ParsedProverTestCase};
+
+    use super::*;
+
+    #[rstest]
+    #[case("0x1234abcd", ProofBytes::Hex { prefixed: true, bytes: vec![0x12, 0x34, 0xab, 0xcd] })]
+    #[case("00ff", ProofBytes::Hex { prefixed: false, bytes: vec![0x00, 0xff] })]
+    #[case("0x", ProofBytes::Hex { prefixed: true, bytes: vec![] })]
+    #[case("0xABCD", ProofBytes::Raw("0xABCD".to_string()))]
+    #[case("0x123", ProofBytes::Raw("0x123".to_string()))]
+    #[case("not hex", ProofBytes::Raw("not hex".to_string()))]
+    fn test_proof_bytes_round_trip(#[case] proof_hex: &str, #[case] expected: ProofBytes) {
+        let proof_bytes = ProofBytes::from_hex(proof_hex);
+        assert_eq!(proof_bytes, expected);
+        assert_eq!(proof_bytes.into_hex(), proof_hex);
+    }
+
+    #[rstest]
+    #[case(b"".as_slice())]
+    #[case(b"STPF".as_slice())]
+    #[case(b"{\"proof_hex\": \"0x\"}".as_slice())]
+    fn test_decode_invalid_magic(#[case] encoded_proof: &[u8]) {
+        assert!(matches!(
+            decode_proof(encoded_proof),
+            Err(ProofEncodingError::InvalidMagic)
+        ));
+    }
+
+    #[rstest]
+    fn test_decode_unsupported_version() {
+        assert!(matches!(
+            decode_proof(b"STPF\x02\x00"),
+            Err(ProofEncodingError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[rstest]
+    fn test_decode_unsupported_compression() {
+        assert!(matches!(
+            decode_proof(b"STPF\x01\x07"),
+            Err(ProofEncodingError::UnsupportedCompression(7))
+        ));
+    }
+
+    /// A payload claiming a huge proof is rejected before allocating it.
+    #[rstest]
+    fn test_decode_payload_over_limit() {
+        let mut encoded_proof = b"STPF\x01\x00".to_vec();
+        // `ProofBytes::Hex`, not prefixed, followed by a varint u64 length of 2⁶⁴ - 1 bytes.
+        encoded_proof.extend_from_slice(&[0, 0, 253]);
+        encoded_proof.extend_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(matches!(
+            decode_proof(&encoded_proof),
+            Err(ProofEncodingError::Decode(
+                bincode::error::DecodeError::LimitExceeded
+            ))
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[rstest]
+    fn test_decode_zstd_payload_over_limit() {
+        use std::io::Read;
+
+        let payload = std::io::repeat(0).take(MAX_PAYLOAD_LEN as u64 + 1);
+        let mut encoded_proof = b"STPF\x01\x01".to_vec();
+        encoded_proof.extend_from_slice(&zstd::encode_all(payload, 1).unwrap());
+
+        assert!(matches!(
+            decode_proof(&encoded_proof),
+            Err(ProofEncodingError::PayloadTooLarge)
+        ));
+    }
+
+    fn assert_encode_decode_round_trip(proof: &Proof, compression: Compression) {
+        let encoded_proof = encode_proof(proof, compression).unwrap();
+        let decoded_proof = decode_proof(&encoded_proof).unwrap();
+
+        assert!(encoded_proof.len() < serde_json::to_vec(proof).unwrap().len());
+        // Compare values, maps do not serialize their keys in a stable order.
+        assert_eq!(
+            serde_json::to_value(&decoded_proof).unwrap(),
+            serde_json::to_value(proof).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn test_encode_decode_proof(parsed_prover_test_case: ParsedProverTestCase) {
+        assert_encode_decode_round_trip(&parsed_prover_test_case.proof, Compression::None);
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[rstest]
+    fn test_encode_proof_with_zstd_requires_feature(parsed_prover_test_case: ParsedProverTestCase) {
+        assert!(matches!(
+            encode_proof(&parsed_prover_test_case.proof, Compression::Zstd(3)),
+            Err(ProofEncodingError::UnsupportedCompression(COMPRESSION_ZSTD))
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[rstest]
+    fn test_encode_decode_proof_with_zstd(parsed_prover_test_case: ParsedProverTestCase) {
+        let proof = parsed_prover_test_case.proof;
+        assert_encode_decode_round_trip(&proof, Compression::Zstd(3));
+
+        let uncompressed_size = encode_proof(&proof, Compression::None).unwrap().len();
+        let compressed_size = encode_proof(&proof, Compression::Zstd(3)).unwrap().len();
+        assert!(compressed_size < uncompressed_size);
+    }
+}
@@ -1,11 +1,18 @@
-use std::path::Path;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use cairo_vm::air_private_input::AirPrivateInput;
-use tempfile::tempdir;
 
+use crate::dynamic_params::validate_dynamic_params;
 use crate::error::ProverError;
 use crate::json::{read_json_from_file, write_json_to_file};
-use crate::models::{Proof, ProverConfig, ProverParameters, ProverWorkingDirectory, PublicInput};
+use crate::models::{
+    Artifact, Proof, ProverConfig, ProverParameters, ProverWorkingDirectory, PublicInput,
+    WorkingDirectoryPolicy,
+};
+use crate::params_validation::validate_prover_parameters;
+use crate::proof_cache::{default_proof_cache, ProofCache, ProofCacheKey};
 
 /// Call the Stone Prover from the command line.
 ///
@@ -27,24 +34,15 @@ pub fn run_prover_from_command_line(
     prover_parameter_file: &Path,
     output_file: &Path,
 ) -> Result<(), ProverError> {
-    let output = std::process::Command::new("cpu_air_prover")
-        .arg("--out-file")
-        .arg(output_file)
-        .arg("--public-input-file")
-        .arg(public_input_file)
-        .arg("--private-input-file")
-        .arg(private_input_file)
-        .arg("--prover-config-file")
-        .arg(prover_config_file)
-        .arg("--parameter-file")
-        .arg(prover_parameter_file)
-        .output()?;
-
-    if !output.status.success() {
-        return Err(ProverError::CommandError(output));
-    }
-
-    Ok(())
+    let mut command = std::process::Command::new("cpu_air_prover");
+    command.args(prover_args(
+        public_input_file,
+        private_input_file,
+        prover_config_file,
+        prover_parameter_file,
+        output_file,
+    ));
+    run_prover_command(command)
 }
 
 /// Call the Stone Prover from the command line, asynchronously.
@@ -67,19 +65,55 @@ pub async fn run_prover_from_command_line_async(
     parameter_file: &Path,
     output_file: &Path,
 ) -> Result<(), ProverError> {
-    let output = tokio::process::Command::new("cpu_air_prover")
-        .arg("--out-file")
-        .arg(output_file)
-        .arg("--public-input-file")
-        .arg(public_input_file)
-        .arg("--private-input-file")
-        .arg(private_input_file)
-        .arg("--prover-config-file")
-        .arg(prover_config_file)
-        .arg("--parameter-file")
-        .arg(parameter_file)
-        .output()
-        .await?;
+    let mut command = std::process::Command::new("cpu_air_prover");
+    command.args(prover_args(
+        public_input_file,
+        private_input_file,
+        prover_config_file,
+        parameter_file,
+        output_file,
+    ));
+    run_prover_command_async(command).await
+}
+
+/// Returns the command-line arguments of `cpu_air_prover`.
+pub(crate) fn prover_args(
+    public_input_file: &Path,
+    private_input_file: &Path,
+    prover_config_file: &Path,
+    parameter_file: &Path,
+    output_file: &Path,
+) -> Vec<OsString> {
+    vec![
+        "--out-file".into(),
+        output_file.into(),
+        "--public-input-file".into(),
+        public_input_file.into(),
+        "--private-input-file".into(),
+        private_input_file.into(),
+        "--prover-config-file".into(),
+        prover_config_file.into(),
+        "--parameter-file".into(),
+        parameter_file.into(),
+    ]
+}
+
+/// Returns the command-line arguments of `cpu_air_prover` for the files of the working
+/// directory.
+pub(crate) fn prover_args_for_working_dir(
+    prover_working_dir: &ProverWorkingDirectory,
+) -> Vec<OsString> {
+    prover_args(
+        &prover_working_dir.public_input_file,
+        &prover_working_dir.private_input_file,
+        &prover_working_dir.prover_config_file,
+        &prover_working_dir.prover_parameter_file,
+        &prover_working_dir.proof_file,
+    )
+}
+
+fn run_prover_command(mut command: std::process::Command) -> Result<(), ProverError> {
+    let output = command.output()?;
 
     if !output.status.success() {
         return Err(ProverError::CommandError(output));
@@ -88,54 +122,137 @@ pub async fn run_prover_from_command_line_async(
     Ok(())
 }
 
+async fn run_prover_command_async(command: std::process::Command) -> Result<(), ProverError> {
+    let output = tokio::process::Command::from(command).output().await?;
+
+    if !output.status.success() {
+        return Err(ProverError::CommandError(output));
+    }
+
+    Ok(())
+}
+
+/// Borrowed memory or trace file, to write the buffers of the callers without copying them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ArtifactRef<'a> {
+    InMemory(&'a [u8]),
+    OnDisk(&'a Path),
+}
+
+impl<'a> From<&'a Artifact> for ArtifactRef<'a> {
+    fn from(artifact: &'a Artifact) -> Self {
+        match artifact {
+            Artifact::InMemory(content) => Self::InMemory(content),
+            Artifact::OnDisk(path) => Self::OnDisk(path),
+        }
+    }
+}
+
+/// Writes an in-memory artifact to `path`. Artifacts already on disk are used in place
+/// to avoid copying large files.
+///
+/// Returns the path to the artifact file.
+fn write_artifact(artifact: ArtifactRef<'_>, path: PathBuf) -> Result<PathBuf, std::io::Error> {
+    match artifact {
+        ArtifactRef::InMemory(content) => {
+            std::fs::write(&path, content)?;
+            Ok(path)
+        }
+        ArtifactRef::OnDisk(artifact_path) => Ok(artifact_path.to_path_buf()),
+    }
+}
+
+/// Creates a new, uniquely named directory in the system temporary directory.
+///
+/// The directory is only accessible to the current user. It is not deleted automatically,
+/// `ProverWorkingDirectory` decides whether to delete it.
+pub(crate) fn create_temp_dir() -> Result<PathBuf, std::io::Error> {
+    Ok(tempfile::Builder::new()
+        .prefix("stone-prover-")
+        .tempdir()?
+        .keep())
+}
+
+/// Creates the working directory of the prover according to the policy.
+///
+/// Returns the path to the directory and whether it must be deleted once the prover is done.
+fn create_working_dir(policy: &WorkingDirectoryPolicy) -> Result<(PathBuf, bool), std::io::Error> {
+    match policy {
+        WorkingDirectoryPolicy::UserSupplied(dir) => {
+            std::fs::create_dir_all(dir)?;
+            Ok((dir.clone(), false))
+        }
+        WorkingDirectoryPolicy::KeepAlways => Ok((create_temp_dir()?, false)),
+        WorkingDirectoryPolicy::TempAutoDelete | WorkingDirectoryPolicy::KeepOnFailure => {
+            Ok((create_temp_dir()?, true))
+        }
+    }
+}
+
 fn prepare_prover_files(
     public_input: &PublicInput,
     private_input: &AirPrivateInput,
-    memory: &Vec<u8>,
-    trace: &Vec<u8>,
+    memory: ArtifactRef<'_>,
+    trace: ArtifactRef<'_>,
     prover_config: &ProverConfig,
     parameters: &ProverParameters,
+    policy: &WorkingDirectoryPolicy,
 ) -> Result<ProverWorkingDirectory, std::io::Error> {
-    let tmp_dir = tempdir()?;
+    let (dir, delete_on_drop) = create_working_dir(policy)?;
+    // Create the working directory object first to clean up if writing a file fails.
+    let mut prover_working_dir = ProverWorkingDirectory::new(dir, delete_on_drop);
 
-    let tmp_dir_path = tmp_dir.path();
-
-    let public_input_file = tmp_dir_path.join("public_input.json");
-    let private_input_file = tmp_dir_path.join("private_input.json");
-    let memory_file = tmp_dir_path.join("memory.bin");
-    let prover_config_file = tmp_dir_path.join("prover_config_file.json");
-    let prover_parameter_file = tmp_dir_path.join("parameters.json");
-    let trace_file = tmp_dir_path.join("trace.bin");
-    let proof_file = tmp_dir_path.join("proof.json");
+    // Write memory and trace files
+    prover_working_dir._memory_file =
+        write_artifact(memory, prover_working_dir._memory_file.clone())?;
+    prover_working_dir._trace_file = write_artifact(trace, prover_working_dir._trace_file.clone())?;
 
     // Write public input and config/parameters files
-    write_json_to_file(public_input, &public_input_file)?;
-    write_json_to_file(prover_config, &prover_config_file)?;
-    write_json_to_file(parameters, &prover_parameter_file)?;
+    write_json_to_file(public_input, &prover_working_dir.public_input_file)?;
+    write_json_to_file(prover_config, &prover_working_dir.prover_config_file)?;
+    write_json_to_file(parameters, &prover_working_dir.prover_parameter_file)?;
 
     // Write private input file
     let private_input_serializable = private_input.to_serializable(
-        trace_file.to_string_lossy().to_string(),
-        memory_file.to_string_lossy().to_string(),
+        prover_working_dir._trace_file.to_string_lossy().to_string(),
+        prover_working_dir
+            ._memory_file
+            .to_string_lossy()
+            .to_string(),
     );
-    write_json_to_file(private_input_serializable, &private_input_file)?;
+    write_json_to_file(
+        private_input_serializable,
+        &prover_working_dir.private_input_file,
+    )?;
 
-    // Write memory and trace files
-    std::fs::write(&memory_file, memory)?;
-    std::fs::write(&trace_file, trace)?;
+    Ok(prover_working_dir)
+}
 
-    Ok(ProverWorkingDirectory {
-        dir: tmp_dir,
-        public_input_file,
-        private_input_file,
-        _memory_file: memory_file,
-        _trace_file: trace_file,
-        prover_config_file,
-        prover_parameter_file,
-        proof_file,
-        annotations_file: None,
-        extra_annotations_file: None,
-    })
+/// Applies the working directory policy after a prover failure.
+///
+/// Unless the policy is `TempAutoDelete`, the working directory is kept and the standard
+/// error of the prover is written to `stderr_log_file`.
+fn handle_prover_failure(
+    error: ProverError,
+    prover_working_dir: &mut ProverWorkingDirectory,
+    policy: &WorkingDirectoryPolicy,
+) -> ProverError {
+    if *policy == WorkingDirectoryPolicy::TempAutoDelete {
+        return error;
+    }
+    prover_working_dir.delete_on_drop = false;
+
+    match error {
+        ProverError::CommandError(output) => {
+            // The prover error is more relevant than a failure to write the log, ignore it.
+            let _ = std::fs::write(&prover_working_dir.stderr_log_file, &output.stderr);
+            ProverError::CommandErrorInWorkingDir {
+                output,
+                working_dir: prover_working_dir.dir.clone(),
+            }
+        }
+        error => error,
+    }
 }
 
 /// Run the Stone Prover on the specified program execution.
@@ -143,12 +260,17 @@ fn prepare_prover_files(
 /// This function abstracts the method used to call the prover. At the moment we invoke
 /// the prover as a subprocess but other methods can be implemented (ex: FFI).
 ///
+/// If a cache was set with `proof_cache::set_default_proof_cache`, the proof is looked up
+/// in the cache before running the prover, see `run_prover_with_cache`. `run_prover_async`
+/// does not use the default cache, as it returns the working directory of the prover.
+///
 /// * `public_input`: the public prover input generated by the Cairo program.
 /// * `private_input`: the private prover input generated by the Cairo program.
 /// * `memory`: the memory output of the Cairo program.
 /// * `trace`: the execution trace of the Cairo program.
 /// * `prover_config`: prover configuration.
 /// * `parameters`: prover parameters for the Cairo program.
+#[allow(clippy::ptr_arg)]
 pub fn run_prover(
     public_input: &PublicInput,
     private_input: &AirPrivateInput,
@@ -157,27 +279,169 @@ pub fn run_prover(
     prover_config: &ProverConfig,
     parameters: &ProverParameters,
 ) -> Result<Proof, ProverError> {
-    let prover_working_dir = prepare_prover_files(
+    run_prover_with_optional_cache(
+        public_input,
+        private_input,
+        ArtifactRef::InMemory(memory),
+        ArtifactRef::InMemory(trace),
+        prover_config,
+        parameters,
+        default_proof_cache().as_deref(),
+    )
+}
+
+/// Run the Stone Prover on the specified program execution, with memory and trace artifacts
+/// that can be held in memory or already written on disk.
+///
+/// Artifacts on disk are passed to the prover in place, without copying them. Like
+/// `run_prover`, this function consults the default proof cache, if any.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `private_input`: the private prover input generated by the Cairo program.
+/// * `memory`: the memory output of the Cairo program.
+/// * `trace`: the execution trace of the Cairo program.
+/// * `prover_config`: prover configuration.
+/// * `parameters`: prover parameters for the Cairo program.
+pub fn run_prover_with_artifacts(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: &Artifact,
+    trace: &Artifact,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+) -> Result<Proof, ProverError> {
+    run_prover_with_optional_cache(
+        public_input,
+        private_input,
+        memory.into(),
+        trace.into(),
+        prover_config,
+        parameters,
+        default_proof_cache().as_deref(),
+    )
+}
+
+/// Looks up the proof in `cache` before running the prover, and adds new proofs to it.
+fn run_prover_with_optional_cache(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: ArtifactRef<'_>,
+    trace: ArtifactRef<'_>,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    cache: Option<&dyn ProofCache>,
+) -> Result<Proof, ProverError> {
+    let cache_entry = match cache {
+        Some(cache) => {
+            let key = ProofCacheKey::from_artifact_refs(
+                public_input,
+                memory,
+                trace,
+                prover_config,
+                parameters,
+            )?;
+            // The cache only saves time: failing to read or write it does not fail the proof.
+            if let Ok(Some(proof)) = cache.get(&key) {
+                return Ok(proof);
+            }
+            Some((cache, key))
+        }
+        None => None,
+    };
+
+    let (proof, _) = run_prover_with_command(
         public_input,
         private_input,
         memory,
         trace,
         prover_config,
         parameters,
+        &WorkingDirectoryPolicy::TempAutoDelete,
+        native_prover_command,
     )?;
+    if let Some((cache, key)) = cache_entry {
+        let _ = cache.put(&key, &proof);
+    }
+    Ok(proof)
+}
 
-    // Call the prover
-    run_prover_from_command_line(
-        &prover_working_dir.public_input_file,
-        &prover_working_dir.private_input_file,
-        &prover_working_dir.prover_config_file,
-        &prover_working_dir.prover_parameter_file,
-        &prover_working_dir.proof_file,
+/// Run the Stone Prover on the specified program execution, with control over the working
+/// directory of the prover.
+///
+/// Use `WorkingDirectoryPolicy::KeepOnFailure` to keep the prover input files if the proof
+/// fails. The error then indicates the location of the files.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `private_input`: the private prover input generated by the Cairo program.
+/// * `memory`: the memory output of the Cairo program.
+/// * `trace`: the execution trace of the Cairo program.
+/// * `prover_config`: prover configuration.
+/// * `parameters`: prover parameters for the Cairo program.
+/// * `policy`: where to write the prover files and when to delete them.
+pub fn run_prover_with_policy(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: &Artifact,
+    trace: &Artifact,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    policy: &WorkingDirectoryPolicy,
+) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+    run_prover_with_command(
+        public_input,
+        private_input,
+        memory.into(),
+        trace.into(),
+        prover_config,
+        parameters,
+        policy,
+        native_prover_command,
+    )
+}
+
+pub(crate) fn native_prover_command(
+    prover_working_dir: &ProverWorkingDirectory,
+) -> std::process::Command {
+    let mut command = std::process::Command::new("cpu_air_prover");
+    command.args(prover_args_for_working_dir(prover_working_dir));
+    command
+}
+
+/// Prepares the prover files and runs the command returned by `make_command` on them.
+///
+/// This lets other backends (ex: containers) reuse the handling of the working directory.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_prover_with_command(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: ArtifactRef<'_>,
+    trace: ArtifactRef<'_>,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    policy: &WorkingDirectoryPolicy,
+    make_command: impl FnOnce(&ProverWorkingDirectory) -> std::process::Command,
+) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+    validate_dynamic_params(public_input).map_err(ProverError::InvalidDynamicParams)?;
+    validate_prover_parameters(public_input, parameters).map_err(ProverError::InvalidParameters)?;
+
+    let mut prover_working_dir = prepare_prover_files(
+        public_input,
+        private_input,
+        memory,
+        trace,
+        prover_config,
+        parameters,
+        policy,
     )?;
 
+    // Call the prover
+    run_prover_command(make_command(&prover_working_dir))
+        .map_err(|e| handle_prover_failure(e, &mut prover_working_dir, policy))?;
+
     // Load the proof from the generated JSON proof file
-    let proof = read_json_from_file(&prover_working_dir.proof_file)?;
-    Ok(proof)
+    let proof = read_json_from_file(&prover_working_dir.proof_file)
+        .map_err(|e| handle_prover_failure(e.into(), &mut prover_working_dir, policy))?;
+    Ok((proof, prover_working_dir))
 }
 
 /// Run the Stone Prover on the specified program execution, asynchronously.
@@ -188,12 +452,16 @@ pub fn run_prover(
 /// This function abstracts the method used to call the prover. At the moment we invoke
 /// the prover as a subprocess but other methods can be implemented (ex: FFI).
 ///
+/// The working directory of the prover is returned with the proof, so the default proof
+/// cache is not consulted. Use `run_prover_with_cache_async` to use a cache.
+///
 /// * `public_input`: the public prover input generated by the Cairo program.
 /// * `private_input`: the private prover input generated by the Cairo program.
 /// * `memory`: the memory output of the Cairo program.
 /// * `trace`: the execution trace of the Cairo program.
 /// * `prover_config`: prover configuration.
 /// * `parameters`: prover parameters for the Cairo program.
+#[allow(clippy::ptr_arg)]
 pub async fn run_prover_async(
     public_input: &PublicInput,
     private_input: &AirPrivateInput,
@@ -202,38 +470,227 @@ pub async fn run_prover_async(
     prover_config: &ProverConfig,
     parameters: &ProverParameters,
 ) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
-    let prover_working_dir = prepare_prover_files(
+    run_prover_with_command_async(
+        public_input,
+        private_input,
+        ArtifactRef::InMemory(memory),
+        ArtifactRef::InMemory(trace),
+        prover_config,
+        parameters,
+        &WorkingDirectoryPolicy::TempAutoDelete,
+        native_prover_command,
+    )
+    .await
+}
+
+/// Asynchronous version of `run_prover_with_artifacts`.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `private_input`: the private prover input generated by the Cairo program.
+/// * `memory`: the memory output of the Cairo program.
+/// * `trace`: the execution trace of the Cairo program.
+/// * `prover_config`: prover configuration.
+/// * `parameters`: prover parameters for the Cairo program.
+pub async fn run_prover_with_artifacts_async(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: &Artifact,
+    trace: &Artifact,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+    run_prover_with_policy_async(
         public_input,
         private_input,
         memory,
         trace,
         prover_config,
         parameters,
+        &WorkingDirectoryPolicy::TempAutoDelete,
+    )
+    .await
+}
+
+/// Run the Stone Prover on the specified program execution asynchronously, with control over
+/// the working directory of the prover.
+///
+/// See `run_prover_with_policy` for details.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `private_input`: the private prover input generated by the Cairo program.
+/// * `memory`: the memory output of the Cairo program.
+/// * `trace`: the execution trace of the Cairo program.
+/// * `prover_config`: prover configuration.
+/// * `parameters`: prover parameters for the Cairo program.
+/// * `policy`: where to write the prover files and when to delete them.
+pub async fn run_prover_with_policy_async(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: &Artifact,
+    trace: &Artifact,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    policy: &WorkingDirectoryPolicy,
+) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+    run_prover_with_command_async(
+        public_input,
+        private_input,
+        memory.into(),
+        trace.into(),
+        prover_config,
+        parameters,
+        policy,
+        native_prover_command,
+    )
+    .await
+}
+
+/// Asynchronous version of `run_prover_with_command`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_prover_with_command_async(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: ArtifactRef<'_>,
+    trace: ArtifactRef<'_>,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    policy: &WorkingDirectoryPolicy,
+    make_command: impl FnOnce(&ProverWorkingDirectory) -> std::process::Command,
+) -> Result<(Proof, ProverWorkingDirectory), ProverError> {
+    validate_dynamic_params(public_input).map_err(ProverError::InvalidDynamicParams)?;
+    validate_prover_parameters(public_input, parameters).map_err(ProverError::InvalidParameters)?;
+
+    let mut prover_working_dir = prepare_prover_files(
+        public_input,
+        private_input,
+        memory,
+        trace,
+        prover_config,
+        parameters,
+        policy,
     )?;
 
     // Call the prover
-    run_prover_from_command_line_async(
-        &prover_working_dir.public_input_file,
-        &prover_working_dir.private_input_file,
-        &prover_working_dir.prover_config_file,
-        &prover_working_dir.prover_parameter_file,
-        &prover_working_dir.proof_file,
-    )
-    .await?;
+    run_prover_command_async(make_command(&prover_working_dir))
+        .await
+        .map_err(|e| handle_prover_failure(e, &mut prover_working_dir, policy))?;
 
     // Load the proof from the generated JSON proof file
-    let proof = read_json_from_file(&prover_working_dir.proof_file)?;
+    let proof = read_json_from_file(&prover_working_dir.proof_file)
+        .map_err(|e| handle_prover_failure(e.into(), &mut prover_working_dir, policy))?;
     Ok((proof, prover_working_dir))
 }
 
+/// Run the Stone Prover on the specified program execution, unless the proof is already
+/// in the cache.
+///
+/// The cache is looked up with a hash of the inputs of the prover. New proofs are added
+/// to the cache. Errors of the cache are ignored: the prover runs if the cache cannot be
+/// read, and the proof is returned if it cannot be stored.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `private_input`: the private prover input generated by the Cairo program.
+/// * `memory`: the memory output of the Cairo program.
+/// * `trace`: the execution trace of the Cairo program.
+/// * `prover_config`: prover configuration.
+/// * `parameters`: prover parameters for the Cairo program.
+/// * `cache`: proof cache.
+pub fn run_prover_with_cache(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: &Artifact,
+    trace: &Artifact,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    cache: &dyn ProofCache,
+) -> Result<Proof, ProverError> {
+    run_prover_with_optional_cache(
+        public_input,
+        private_input,
+        memory.into(),
+        trace.into(),
+        prover_config,
+        parameters,
+        Some(cache),
+    )
+}
+
+/// Run the Stone Prover on the specified program execution asynchronously, unless the proof
+/// is already in the cache.
+///
+/// See `run_prover_with_cache` for details. Hashing the inputs and accessing the cache
+/// block, so they run on the blocking threads of the runtime. The inputs are copied to
+/// be hashed there.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `private_input`: the private prover input generated by the Cairo program.
+/// * `memory`: the memory output of the Cairo program.
+/// * `trace`: the execution trace of the Cairo program.
+/// * `prover_config`: prover configuration.
+/// * `parameters`: prover parameters for the Cairo program.
+/// * `cache`: proof cache.
+pub async fn run_prover_with_cache_async(
+    public_input: &PublicInput,
+    private_input: &AirPrivateInput,
+    memory: &Artifact,
+    trace: &Artifact,
+    prover_config: &ProverConfig,
+    parameters: &ProverParameters,
+    cache: Arc<dyn ProofCache>,
+) -> Result<Proof, ProverError> {
+    let key = {
+        let public_input = public_input.clone();
+        let memory = memory.clone();
+        let trace = trace.clone();
+        let prover_config = prover_config.clone();
+        let parameters = parameters.clone();
+        tokio::task::spawn_blocking(move || {
+            ProofCacheKey::new(&public_input, &memory, &trace, &prover_config, &parameters)
+        })
+        .await
+        .map_err(|e| ProverError::JobAborted(e.to_string()))??
+    };
+
+    let cached_proof = {
+        let cache = cache.clone();
+        let key = key.clone();
+        tokio::task::spawn_blocking(move || cache.get(&key)).await
+    };
+    if let Ok(Ok(Some(proof))) = cached_proof {
+        return Ok(proof);
+    }
+
+    let (proof, _) = run_prover_with_artifacts_async(
+        public_input,
+        private_input,
+        memory,
+        trace,
+        prover_config,
+        parameters,
+    )
+    .await?;
+    tokio::task::spawn_blocking(move || {
+        let _ = cache.put(&key, &proof);
+        proof
+    })
+    .await
+    .map_err(|e| ProverError::JobAborted(e.to_string()))
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
     use rstest::rstest;
     use tempfile::NamedTempFile;
 
+    use crate::fri::generate_prover_parameters;
+    use crate::models::{Layout, Verifier};
+    use crate::proof_cache::{EvictionPolicy, FsProofCache, ProofCacheError, ProofCacheStats};
     use crate::test_utils::{
-        parsed_prover_test_case, prover_cli_test_case, read_proof_file, ParsedProverTestCase,
-        ProverCliTestCase,
+        parsed_prover_test_case, prover_cli_test_case, prover_test_case, read_proof_file,
+        ParsedProverTestCase, ProverCliTestCase, ProverTestCase,
     };
 
     use super::*;
@@ -244,7 +701,7 @@ mod test {
         let output_file = NamedTempFile::new().expect("Creating output file failed");
         run_prover_from_command_line(
             &prover_cli_test_case.public_input_file,
-            &prover_cli_test_case.private_input_file.path(),
+            prover_cli_test_case.private_input_file.path(),
             &prover_cli_test_case.prover_config_file,
             &prover_cli_test_case.prover_parameter_file,
             output_file.path(),
@@ -270,6 +727,132 @@ mod test {
         assert_eq!(proof.proof_hex, parsed_prover_test_case.proof.proof_hex);
     }
 
+    /// Check that the prover can use memory and trace files that are already on disk.
+    #[rstest]
+    fn test_run_prover_with_artifacts_on_disk(
+        parsed_prover_test_case: ParsedProverTestCase,
+        prover_test_case: ProverTestCase,
+    ) {
+        let proof = run_prover_with_artifacts(
+            &parsed_prover_test_case.public_input,
+            &parsed_prover_test_case.private_input,
+            &Artifact::OnDisk(prover_test_case.memory_file),
+            &Artifact::OnDisk(prover_test_case.trace_file),
+            &parsed_prover_test_case.prover_config,
+            &parsed_prover_test_case.prover_parameters,
+        )
+        .unwrap();
+
+        assert_eq!(proof.proof_hex, parsed_prover_test_case.proof.proof_hex);
+    }
+
+    /// A cache that fails on every access.
+    struct FailingProofCache;
+
+    impl ProofCache for FailingProofCache {
+        fn get(&self, _key: &ProofCacheKey) -> Result<Option<Proof>, ProofCacheError> {
+            Err(std::io::Error::other("cache unavailable").into())
+        }
+
+        fn put(&self, _key: &ProofCacheKey, _proof: &Proof) -> Result<(), ProofCacheError> {
+            Err(std::io::Error::other("cache unavailable").into())
+        }
+
+        fn stats(&self) -> ProofCacheStats {
+            ProofCacheStats::default()
+        }
+    }
+
+    /// Adds a proof with a dummy proof hex to `cache`, for the inputs of the test case.
+    ///
+    /// Returns the dummy proof hex.
+    fn put_cached_proof(cache: &dyn ProofCache, test_case: ParsedProverTestCase) -> String {
+        let key = ProofCacheKey::new(
+            &test_case.public_input,
+            &Artifact::InMemory(test_case.memory),
+            &Artifact::InMemory(test_case.trace),
+            &test_case.prover_config,
+            &test_case.prover_parameters,
+        )
+        .unwrap();
+        let cached_proof = Proof {
+            proof_hex: "0x00".to_string(),
+            ..test_case.proof
+        };
+        cache.put(&key, &cached_proof).unwrap();
+        cached_proof.proof_hex
+    }
+
+    /// Check that `run_prover_with_cache` returns the cached proof without running the prover.
+    #[rstest]
+    fn test_run_prover_with_cache(
+        parsed_prover_test_case: ParsedProverTestCase,
+        #[from(parsed_prover_test_case)] cached_test_case: ParsedProverTestCase,
+    ) {
+        let dir = create_temp_dir().unwrap();
+        let cache = FsProofCache::new(dir.clone(), EvictionPolicy::Never).unwrap();
+        let cached_proof_hex = put_cached_proof(&cache, cached_test_case);
+
+        let proof = run_prover_with_cache(
+            &parsed_prover_test_case.public_input,
+            &parsed_prover_test_case.private_input,
+            &Artifact::InMemory(parsed_prover_test_case.memory),
+            &Artifact::InMemory(parsed_prover_test_case.trace),
+            &parsed_prover_test_case.prover_config,
+            &parsed_prover_test_case.prover_parameters,
+            &cache,
+        )
+        .unwrap();
+
+        assert_eq!(proof.proof_hex, cached_proof_hex);
+        assert_eq!(cache.stats().hits, 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_run_prover_with_cache_async(
+        parsed_prover_test_case: ParsedProverTestCase,
+        #[from(parsed_prover_test_case)] cached_test_case: ParsedProverTestCase,
+    ) {
+        let dir = create_temp_dir().unwrap();
+        let cache = Arc::new(FsProofCache::new(dir.clone(), EvictionPolicy::Never).unwrap());
+        let cached_proof_hex = put_cached_proof(cache.as_ref(), cached_test_case);
+
+        let proof = run_prover_with_cache_async(
+            &parsed_prover_test_case.public_input,
+            &parsed_prover_test_case.private_input,
+            &Artifact::InMemory(parsed_prover_test_case.memory),
+            &Artifact::InMemory(parsed_prover_test_case.trace),
+            &parsed_prover_test_case.prover_config,
+            &parsed_prover_test_case.prover_parameters,
+            cache.clone(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(proof.proof_hex, cached_proof_hex);
+        assert_eq!(cache.stats().hits, 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Check that the prover runs when the cache cannot be read or written.
+    #[rstest]
+    fn test_run_prover_ignores_cache_errors(parsed_prover_test_case: ParsedProverTestCase) {
+        let proof = run_prover_with_cache(
+            &parsed_prover_test_case.public_input,
+            &parsed_prover_test_case.private_input,
+            &Artifact::InMemory(parsed_prover_test_case.memory),
+            &Artifact::InMemory(parsed_prover_test_case.trace),
+            &parsed_prover_test_case.prover_config,
+            &parsed_prover_test_case.prover_parameters,
+            &FailingProofCache,
+        )
+        .unwrap();
+
+        assert_eq!(proof.proof_hex, parsed_prover_test_case.proof.proof_hex);
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_run_prover_async(parsed_prover_test_case: ParsedProverTestCase) {
@@ -286,4 +869,60 @@ mod test {
 
         assert_eq!(proof.0.proof_hex, parsed_prover_test_case.proof.proof_hex);
     }
+
+    #[rstest]
+    #[case(WorkingDirectoryPolicy::TempAutoDelete, false)]
+    #[case(WorkingDirectoryPolicy::KeepOnFailure, false)]
+    #[case(WorkingDirectoryPolicy::KeepAlways, true)]
+    fn test_working_dir_deleted_on_drop(
+        #[case] policy: WorkingDirectoryPolicy,
+        #[case] expected_exists: bool,
+    ) {
+        let (dir, delete_on_drop) = create_working_dir(&policy).unwrap();
+        let working_dir = ProverWorkingDirectory::new(dir.clone(), delete_on_drop);
+        drop(working_dir);
+
+        assert_eq!(dir.exists(), expected_exists);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Check that the working directory and the standard error of the prover are kept
+    /// when the prover fails.
+    #[rstest]
+    fn test_keep_working_dir_on_failure() {
+        let public_input = PublicInput {
+            layout: Layout::Plain,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: 1 << 10,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: None,
+        };
+        let parameters = generate_prover_parameters(public_input.n_steps, Verifier::Stone);
+        let failing_command = |_: &ProverWorkingDirectory| {
+            let mut command = std::process::Command::new("sh");
+            command.args(["-c", "echo 'prover failed' >&2; exit 1"]);
+            command
+        };
+
+        let result = run_prover_with_command(
+            &public_input,
+            &AirPrivateInput(HashMap::new()),
+            ArtifactRef::InMemory(&[]),
+            ArtifactRef::InMemory(&[]),
+            &ProverConfig::default(),
+            &parameters,
+            &WorkingDirectoryPolicy::KeepOnFailure,
+            failing_command,
+        );
+
+        let Err(ProverError::CommandErrorInWorkingDir { working_dir, .. }) = result else {
+            panic!("Expected a prover error with the working directory");
+        };
+        assert!(working_dir.join("public_input.json").exists());
+        let stderr_log = std::fs::read_to_string(working_dir.join("prover_stderr.log")).unwrap();
+        assert_eq!(stderr_log, "prover failed\n");
+        std::fs::remove_dir_all(working_dir).unwrap();
+    }
 }
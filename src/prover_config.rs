@@ -0,0 +1,402 @@
+//! Presets and automatic tuning of the prover configuration.
+//!
+//! The prover configuration does not change the proof, only how the prover trades memory
+//! for speed. The memory estimates of this module are coarse and meant to pick a configuration
+//! that fits on the machine, not to predict the exact memory usage of the prover.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::models::{CachedLdeConfig, ProverConfig, ProverParameters, PublicInput};
+
+const FIELD_ELEMENT_BYTES: u64 = 32;
+const HASH_BYTES: u64 = 32;
+/// Maximum number of Merkle layers kept out of memory by the tuner.
+const MAX_OUT_OF_MEMORY_MERKLE_LAYERS: i32 = 8;
+/// Number of constraint evaluation tasks per core, to balance the load between threads.
+const CONSTRAINT_TASKS_PER_CORE: u64 = 64;
+const MIN_CONSTRAINT_POLYNOMIAL_TASK_SIZE: u64 = 64;
+const MAX_CONSTRAINT_POLYNOMIAL_TASK_SIZE: u64 = 4096;
+
+/// Named prover configurations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverConfigPreset {
+    /// Recompute more Merkle layers to reduce memory usage. The LDE is recomputed when needed,
+    /// as with the balanced preset.
+    LowMemory,
+    /// Default configuration of the SDK.
+    Balanced,
+    /// Store the full LDE and all the Merkle layers in memory.
+    MaxSpeed,
+}
+
+impl ProverConfigPreset {
+    pub fn prover_config(&self) -> ProverConfig {
+        match self {
+            Self::LowMemory => ProverConfig {
+                cached_lde_config: CachedLdeConfig {
+                    store_full_lde: false,
+                    use_fft_for_eval: false,
+                },
+                n_out_of_memory_merkle_layers: 4,
+                ..ProverConfig::default()
+            },
+            Self::Balanced => ProverConfig::default(),
+            Self::MaxSpeed => ProverConfig {
+                // Evaluating with FFTs is only useful when the LDE is recomputed.
+                cached_lde_config: CachedLdeConfig {
+                    store_full_lde: true,
+                    use_fft_for_eval: false,
+                },
+                n_out_of_memory_merkle_layers: 0,
+                ..ProverConfig::default()
+            },
+        }
+    }
+}
+
+impl FromStr for ProverConfigPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let preset = match s {
+            "low-memory" => Self::LowMemory,
+            "balanced" => Self::Balanced,
+            "max-speed" => Self::MaxSpeed,
+            other => {
+                return Err(format!("unknown prover config preset: {other}"));
+            }
+        };
+
+        Ok(preset)
+    }
+}
+
+impl Display for ProverConfigPreset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::LowMemory => "low-memory",
+            Self::Balanced => "balanced",
+            Self::MaxSpeed => "max-speed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Resources of the machine running the prover.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemResources {
+    /// Memory available to the prover, in bytes. `None` if unknown.
+    pub available_memory: Option<u64>,
+    pub n_cores: usize,
+}
+
+impl SystemResources {
+    /// Detects the resources of the current machine.
+    ///
+    /// The available memory is only detected on Linux.
+    pub fn detect() -> Self {
+        Self {
+            available_memory: detect_available_memory(),
+            n_cores: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn detect_available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn detect_available_memory() -> Option<u64> {
+    None
+}
+
+/// Estimates the memory used by the prover, in bytes.
+///
+/// Storing the full LDE multiplies the memory of the trace by the blowup factor, and each
+/// Merkle layer kept out of memory halves the memory of the Merkle trees.
+///
+/// The estimate saturates at `u64::MAX` instead of overflowing for huge traces or blowup
+/// factors.
+///
+/// * `trace_length`: Number of rows of the trace, see `PublicInput::trace_length`.
+/// * `n_columns`: Number of columns of the trace, see `LayoutParams::n_columns`.
+/// * `log_n_cosets`: log₂ of the blowup factor.
+/// * `prover_config`: prover configuration.
+pub fn estimate_prover_memory(
+    trace_length: u32,
+    n_columns: u32,
+    log_n_cosets: u32,
+    prover_config: &ProverConfig,
+) -> u64 {
+    let trace_length = trace_length as u64;
+    let n_cosets = n_cosets(log_n_cosets);
+
+    let trace_memory = (trace_length * n_columns as u64).saturating_mul(FIELD_ELEMENT_BYTES);
+    let lde_memory = match prover_config.cached_lde_config.store_full_lde {
+        true => trace_memory.saturating_mul(n_cosets),
+        false => trace_memory,
+    };
+    let out_of_memory_layers = prover_config.n_out_of_memory_merkle_layers.clamp(0, 63) as u32;
+    let merkle_memory = match trace_length
+        .saturating_mul(n_cosets)
+        .saturating_mul(2 * HASH_BYTES)
+    {
+        u64::MAX => u64::MAX,
+        merkle_memory => merkle_memory >> out_of_memory_layers,
+    };
+
+    trace_memory
+        .saturating_add(lde_memory)
+        .saturating_add(merkle_memory)
+}
+
+/// Returns the blowup factor, saturating at `u64::MAX` if `log_n_cosets` is 64 or more.
+fn n_cosets(log_n_cosets: u32) -> u64 {
+    1u64.checked_shl(log_n_cosets).unwrap_or(u64::MAX)
+}
+
+/// Picks a prover configuration for a program execution and the resources of the machine.
+///
+/// The tuner picks the fastest preset that fits in the available memory, keeping more Merkle
+/// layers out of memory if even the low-memory preset does not fit. The task sizes are then
+/// adapted to the number of cores. If the available memory is unknown, the balanced preset
+/// is used.
+///
+/// * `public_input`: the public prover input generated by the Cairo program.
+/// * `parameters`: prover parameters for the Cairo program.
+/// * `resources`: resources of the machine running the prover.
+pub fn auto_tune_prover_config(
+    public_input: &PublicInput,
+    parameters: &ProverParameters,
+    resources: &SystemResources,
+) -> ProverConfig {
+    // Traces too long to be proven get the configuration using the least memory.
+    let trace_length = public_input.trace_length().unwrap_or(u32::MAX);
+    let n_columns = public_input.layout.params().n_columns;
+    let log_n_cosets = parameters.stark.log_n_cosets.max(0) as u32;
+    let fits = |prover_config: &ProverConfig| match resources.available_memory {
+        Some(available_memory) => {
+            estimate_prover_memory(trace_length, n_columns, log_n_cosets, prover_config)
+                <= available_memory
+        }
+        None => false,
+    };
+
+    let mut prover_config = match resources.available_memory {
+        None => ProverConfigPreset::Balanced.prover_config(),
+        Some(_) => [ProverConfigPreset::MaxSpeed, ProverConfigPreset::Balanced]
+            .iter()
+            .map(|preset| preset.prover_config())
+            .find(|prover_config| fits(prover_config))
+            .unwrap_or_else(|| ProverConfigPreset::LowMemory.prover_config()),
+    };
+    // Best effort: the prover may still run out of memory if the machine is too small.
+    while resources.available_memory.is_some()
+        && !fits(&prover_config)
+        && prover_config.n_out_of_memory_merkle_layers < MAX_OUT_OF_MEMORY_MERKLE_LAYERS
+    {
+        prover_config.n_out_of_memory_merkle_layers += 1;
+    }
+
+    let n_cores = resources.n_cores.max(1) as u64;
+    let evaluation_domain_size = (trace_length as u64).saturating_mul(n_cosets(log_n_cosets));
+    let task_size = (evaluation_domain_size / (n_cores * CONSTRAINT_TASKS_PER_CORE))
+        .clamp(
+            MIN_CONSTRAINT_POLYNOMIAL_TASK_SIZE,
+            MAX_CONSTRAINT_POLYNOMIAL_TASK_SIZE,
+        )
+        .next_power_of_two();
+    prover_config.constraint_polynomial_task_size = task_size as i32;
+    prover_config.table_prover_n_tasks_per_segment = (n_cores.next_power_of_two() as i32)
+        .max(ProverConfig::default().table_prover_n_tasks_per_segment);
+
+    prover_config
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use crate::fri::generate_prover_parameters;
+    use crate::models::{Layout, Verifier};
+
+    use super::*;
+
+    const GIB: u64 = 1 << 30;
+
+    fn public_input(layout: Layout, n_steps: u32) -> PublicInput {
+        PublicInput {
+            layout,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: None,
+        }
+    }
+
+    fn tune(layout: &Layout, n_steps: u32, available_memory: Option<u64>) -> ProverConfig {
+        let parameters = generate_prover_parameters(n_steps, Verifier::Stone);
+        let resources = SystemResources {
+            available_memory,
+            n_cores: 8,
+        };
+        auto_tune_prover_config(
+            &public_input(layout.clone(), n_steps),
+            &parameters,
+            &resources,
+        )
+    }
+
+    #[rstest]
+    #[case("low-memory", ProverConfigPreset::LowMemory)]
+    #[case("balanced", ProverConfigPreset::Balanced)]
+    #[case("max-speed", ProverConfigPreset::MaxSpeed)]
+    fn test_preset_string_round_trip(#[case] name: &str, #[case] preset: ProverConfigPreset) {
+        assert_eq!(ProverConfigPreset::from_str(name).unwrap(), preset);
+        assert_eq!(preset.to_string(), name);
+    }
+
+    #[rstest]
+    fn test_presets_memory_order() {
+        let memory = |preset: ProverConfigPreset| {
+            estimate_prover_memory(1 << 20, 15, 4, &preset.prover_config())
+        };
+        assert!(memory(ProverConfigPreset::LowMemory) < memory(ProverConfigPreset::Balanced));
+        assert!(memory(ProverConfigPreset::Balanced) < memory(ProverConfigPreset::MaxSpeed));
+    }
+
+    #[rstest]
+    #[case(ProverConfigPreset::LowMemory)]
+    #[case(ProverConfigPreset::Balanced)]
+    #[case(ProverConfigPreset::MaxSpeed)]
+    fn test_presets_do_not_use_fft_with_full_lde(#[case] preset: ProverConfigPreset) {
+        let cached_lde_config = preset.prover_config().cached_lde_config;
+        assert!(!(cached_lde_config.store_full_lde && cached_lde_config.use_fft_for_eval));
+    }
+
+    /// For 2^18 steps of the starknet_with_keccak layout, the max-speed preset needs about
+    /// 36 GiB, the balanced preset 5.75 GiB and the low-memory preset 4 GiB. Each additional
+    /// Merkle layer kept out of memory saves half of the remaining 0.25 GiB.
+    #[rstest]
+    #[case::max_speed(64 * GIB, ProverConfigPreset::MaxSpeed, 0)]
+    #[case::balanced(32 * GIB, ProverConfigPreset::Balanced, 1)]
+    #[case::low_memory(5 * GIB, ProverConfigPreset::LowMemory, 4)]
+    #[case::more_out_of_memory_layers(31 * GIB / 8, ProverConfigPreset::LowMemory, 5)]
+    #[case::does_not_fit(GIB, ProverConfigPreset::LowMemory, MAX_OUT_OF_MEMORY_MERKLE_LAYERS)]
+    fn test_auto_tune_picks_fastest_config_that_fits(
+        #[case] available_memory: u64,
+        #[case] preset: ProverConfigPreset,
+        #[case] n_out_of_memory_merkle_layers: i32,
+    ) {
+        let prover_config = tune(&Layout::StarknetWithKeccak, 1 << 18, Some(available_memory));
+
+        assert_eq!(
+            prover_config,
+            ProverConfig {
+                cached_lde_config: preset.prover_config().cached_lde_config,
+                constraint_polynomial_task_size: 4096,
+                n_out_of_memory_merkle_layers,
+                table_prover_n_tasks_per_segment: 32,
+            }
+        );
+    }
+
+    #[rstest]
+    #[case(4)]
+    #[case(64)]
+    #[case(u32::MAX)]
+    fn test_estimate_prover_memory_saturates(#[case] log_n_cosets: u32) {
+        let prover_config = ProverConfigPreset::MaxSpeed.prover_config();
+        assert_eq!(
+            estimate_prover_memory(u32::MAX, u32::MAX, log_n_cosets, &prover_config),
+            u64::MAX
+        );
+    }
+
+    /// Blowup factors too large for the memory estimates get the configuration using the least
+    /// memory instead of overflowing.
+    #[rstest]
+    fn test_auto_tune_huge_blowup_factor() {
+        let n_steps = 1 << 18;
+        let mut parameters = generate_prover_parameters(n_steps, Verifier::Stone);
+        parameters.stark.log_n_cosets = 64;
+        let resources = SystemResources {
+            available_memory: Some(64 * GIB),
+            n_cores: 8,
+        };
+
+        let prover_config = auto_tune_prover_config(
+            &public_input(Layout::StarknetWithKeccak, n_steps),
+            &parameters,
+            &resources,
+        );
+
+        assert!(!prover_config.cached_lde_config.store_full_lde);
+        assert_eq!(
+            prover_config.n_out_of_memory_merkle_layers,
+            MAX_OUT_OF_MEMORY_MERKLE_LAYERS
+        );
+        assert_eq!(prover_config.constraint_polynomial_task_size, 4096);
+    }
+
+    /// Layouts with more columns need more memory for the same number of steps.
+    #[rstest]
+    fn test_auto_tune_depends_on_layout() {
+        let n_steps = 1 << 18;
+        let available_memory = Some(32 * GIB);
+
+        let plain_config = tune(&Layout::Plain, n_steps, available_memory);
+        let all_solidity_config = tune(&Layout::AllSolidity, n_steps, available_memory);
+
+        assert!(plain_config.cached_lde_config.store_full_lde);
+        assert!(!all_solidity_config.cached_lde_config.store_full_lde);
+    }
+
+    #[rstest]
+    fn test_auto_tune_unknown_memory() {
+        let prover_config = tune(&Layout::StarknetWithKeccak, 1 << 18, None);
+
+        assert_eq!(
+            prover_config.cached_lde_config,
+            ProverConfigPreset::Balanced
+                .prover_config()
+                .cached_lde_config
+        );
+        assert_eq!(prover_config.n_out_of_memory_merkle_layers, 1);
+        assert_eq!(prover_config.constraint_polynomial_task_size, 4096);
+        assert_eq!(prover_config.table_prover_n_tasks_per_segment, 32);
+    }
+
+    #[rstest]
+    fn test_auto_tune_task_sizes_follow_cores() {
+        let n_steps = 1 << 10;
+        let parameters = generate_prover_parameters(n_steps, Verifier::Stone);
+        let resources = SystemResources {
+            available_memory: None,
+            n_cores: 64,
+        };
+
+        let prover_config = auto_tune_prover_config(
+            &public_input(Layout::StarknetWithKeccak, n_steps),
+            &parameters,
+            &resources,
+        );
+
+        assert_eq!(prover_config.constraint_polynomial_task_size, 64);
+        assert_eq!(prover_config.table_prover_n_tasks_per_segment, 64);
+    }
+}
@@ -0,0 +1,499 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+
+use cairo_vm::air_private_input::AirPrivateInput;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::error::ProverError;
+use crate::models::{
+    Artifact, Proof, ProverConfig, ProverParameters, ProverWorkingDirectory, PublicInput,
+    WorkingDirectoryPolicy,
+};
+use crate::prover::{native_prover_command, run_prover_with_command_async};
+
+/// Default estimate of the memory used by the prover per byte of memory and trace files.
+const DEFAULT_MEMORY_PER_INPUT_BYTE: u64 = 32;
+
+/// Identifier of a job submitted to a `ProverPool`.
+pub type JobId = u64;
+
+/// Inputs of a proof, as passed to `run_prover_with_artifacts_async`.
+#[derive(Debug)]
+pub struct ProveJob {
+    pub public_input: PublicInput,
+    pub private_input: AirPrivateInput,
+    pub memory: Artifact,
+    pub trace: Artifact,
+    pub prover_config: ProverConfig,
+    pub parameters: ProverParameters,
+    /// Jobs with a higher priority are started first. Jobs with the same priority are started
+    /// in submission order.
+    pub priority: i32,
+}
+
+impl ProveJob {
+    /// Estimates the memory used by the prover for this job, from the size of its inputs.
+    fn estimated_memory(&self, memory_per_input_byte: u64) -> u64 {
+        let input_size = artifact_size(&self.memory) + artifact_size(&self.trace);
+        input_size.saturating_mul(memory_per_input_byte)
+    }
+}
+
+fn artifact_size(artifact: &Artifact) -> u64 {
+    match artifact {
+        Artifact::InMemory(content) => content.len() as u64,
+        // If the file cannot be read, the prover will fail anyway.
+        Artifact::OnDisk(path) => std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
+
+/// Result of a job, sent on the result stream of the pool.
+#[derive(Debug)]
+pub struct ProveJobResult {
+    pub job_id: JobId,
+    pub result: Result<Proof, ProverError>,
+}
+
+/// Resources available to the jobs of a `ProverPool`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProverPoolConfig {
+    /// Maximum number of prover processes running at the same time.
+    pub max_concurrent_jobs: usize,
+    /// Maximum memory used by all running jobs, in bytes.
+    pub memory_budget: u64,
+    /// Estimated memory used by the prover per byte of memory and trace files.
+    pub memory_per_input_byte: u64,
+}
+
+impl Default for ProverPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_jobs: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            memory_budget: u64::MAX,
+            memory_per_input_byte: DEFAULT_MEMORY_PER_INPUT_BYTE,
+        }
+    }
+}
+
+/// Job waiting in the queue of the pool.
+#[derive(Debug)]
+struct QueuedJob<T> {
+    job_id: JobId,
+    priority: i32,
+    estimated_memory: u64,
+    job: T,
+}
+
+impl<T> PartialEq for QueuedJob<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for QueuedJob<T> {}
+
+impl<T> PartialOrd for QueuedJob<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for QueuedJob<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Job IDs are assigned in submission order, older jobs come first.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.job_id.cmp(&self.job_id))
+    }
+}
+
+/// Queue and resource accounting of the pool.
+#[derive(Debug)]
+struct Scheduler<T> {
+    config: ProverPoolConfig,
+    queue: BinaryHeap<QueuedJob<T>>,
+    next_job_id: JobId,
+    running_jobs: usize,
+    used_memory: u64,
+}
+
+impl<T> Scheduler<T> {
+    fn new(config: ProverPoolConfig) -> Self {
+        Self {
+            config,
+            queue: BinaryHeap::new(),
+            next_job_id: 0,
+            running_jobs: 0,
+            used_memory: 0,
+        }
+    }
+
+    fn push(&mut self, job: T, priority: i32, estimated_memory: u64) -> JobId {
+        let job_id = self.next_job_id;
+        self.next_job_id += 1;
+        self.queue.push(QueuedJob {
+            job_id,
+            priority,
+            estimated_memory,
+            job,
+        });
+        job_id
+    }
+
+    /// Removes the next job from the queue if there are enough resources to start it.
+    ///
+    /// Jobs are started in priority order: a job that does not fit in the budget blocks
+    /// the jobs behind it, so that large jobs are not starved. A job larger than the whole
+    /// budget is started once no other job is running.
+    fn pop_startable(&mut self) -> Option<QueuedJob<T>> {
+        let next_job = self.queue.peek()?;
+        if self.running_jobs >= self.config.max_concurrent_jobs.max(1) {
+            return None;
+        }
+        let fits_in_budget =
+            self.used_memory.saturating_add(next_job.estimated_memory) <= self.config.memory_budget;
+        if !fits_in_budget && self.running_jobs > 0 {
+            return None;
+        }
+
+        let job = self.queue.pop()?;
+        self.running_jobs += 1;
+        self.used_memory = self.used_memory.saturating_add(job.estimated_memory);
+        Some(job)
+    }
+
+    fn release(&mut self, estimated_memory: u64) {
+        self.running_jobs -= 1;
+        self.used_memory = self.used_memory.saturating_sub(estimated_memory);
+    }
+}
+
+/// Builds the prover command of a job, see `prover::run_prover_with_command`.
+type ProverCommandFn = dyn Fn(&ProverWorkingDirectory) -> std::process::Command + Send + Sync;
+
+struct PoolState {
+    scheduler: Mutex<Scheduler<ProveJob>>,
+    results: UnboundedSender<ProveJobResult>,
+    make_command: Arc<ProverCommandFn>,
+}
+
+/// Runs many prove jobs in parallel, within a CPU and memory budget.
+///
+/// Jobs are queued by priority and started as soon as resources are available. Results are
+/// sent on the stream returned by `ProverPool::new`, in completion order. The stream ends
+/// once the pool is dropped and all the jobs are complete.
+///
+/// The pool spawns its jobs on the current Tokio runtime.
+pub struct ProverPool {
+    state: Arc<PoolState>,
+}
+
+impl ProverPool {
+    /// Creates a pool and the stream on which the results of the jobs are sent.
+    ///
+    /// * `config`: Resources available to the jobs.
+    pub fn new(config: ProverPoolConfig) -> (Self, UnboundedReceiver<ProveJobResult>) {
+        Self::with_command(config, Arc::new(native_prover_command))
+    }
+
+    fn with_command(
+        config: ProverPoolConfig,
+        make_command: Arc<ProverCommandFn>,
+    ) -> (Self, UnboundedReceiver<ProveJobResult>) {
+        let (results, receiver) = unbounded_channel();
+        let state = PoolState {
+            scheduler: Mutex::new(Scheduler::new(config)),
+            results,
+            make_command,
+        };
+        let pool = Self {
+            state: Arc::new(state),
+        };
+        (pool, receiver)
+    }
+
+    /// Adds a job to the queue and returns its ID. The result of the job is sent on the result
+    /// stream with this ID.
+    ///
+    /// Must be called from a Tokio runtime.
+    ///
+    /// * `job`: Inputs of the proof.
+    pub fn submit(&self, job: ProveJob) -> JobId {
+        let job_id = {
+            let mut scheduler = self.state.scheduler.lock().unwrap();
+            let estimated_memory = job.estimated_memory(scheduler.config.memory_per_input_byte);
+            let priority = job.priority;
+            scheduler.push(job, priority, estimated_memory)
+        };
+        start_jobs(&self.state);
+        job_id
+    }
+
+    /// Number of jobs waiting for resources.
+    pub fn queued_jobs(&self) -> usize {
+        self.state.scheduler.lock().unwrap().queue.len()
+    }
+
+    /// Number of jobs currently being proven.
+    pub fn running_jobs(&self) -> usize {
+        self.state.scheduler.lock().unwrap().running_jobs
+    }
+}
+
+/// Starts as many queued jobs as the budget allows.
+fn start_jobs(state: &Arc<PoolState>) {
+    loop {
+        let Some(queued_job) = state.scheduler.lock().unwrap().pop_startable() else {
+            return;
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            let QueuedJob {
+                job_id,
+                estimated_memory,
+                job,
+                ..
+            } = queued_job;
+            // Run the job in its own task so that the resources of the job are released
+            // even if it panics.
+            let make_command = state.make_command.clone();
+            let job_task = tokio::spawn(async move {
+                run_prover_with_command_async(
+                    &job.public_input,
+                    &job.private_input,
+                    (&job.memory).into(),
+                    (&job.trace).into(),
+                    &job.prover_config,
+                    &job.parameters,
+                    &WorkingDirectoryPolicy::TempAutoDelete,
+                    |prover_working_dir| make_command(prover_working_dir),
+                )
+                .await
+                .map(|(proof, _)| proof)
+            });
+            let result = job_task
+                .await
+                .unwrap_or_else(|e| Err(ProverError::JobAborted(e.to_string())));
+
+            state.scheduler.lock().unwrap().release(estimated_memory);
+            // The receiver may have been dropped, the result is not needed anymore in this case.
+            let _ = state.results.send(ProveJobResult { job_id, result });
+            start_jobs(&state);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    use crate::fri::generate_prover_parameters;
+    use crate::models::{Layout, Verifier};
+    use crate::prover::create_temp_dir;
+
+    use super::*;
+
+    fn make_scheduler(max_concurrent_jobs: usize, memory_budget: u64) -> Scheduler<&'static str> {
+        Scheduler::new(ProverPoolConfig {
+            max_concurrent_jobs,
+            memory_budget,
+            memory_per_input_byte: 1,
+        })
+    }
+
+    #[test]
+    fn test_scheduler_priority_order() {
+        let mut scheduler = make_scheduler(3, u64::MAX);
+        scheduler.push("low", 0, 0);
+        scheduler.push("high", 10, 0);
+        scheduler.push("low-2", 0, 0);
+
+        let started: Vec<_> = std::iter::from_fn(|| scheduler.pop_startable())
+            .map(|queued_job| queued_job.job)
+            .collect();
+        assert_eq!(started, vec!["high", "low", "low-2"]);
+    }
+
+    #[test]
+    fn test_scheduler_concurrency_limit() {
+        let mut scheduler = make_scheduler(1, u64::MAX);
+        scheduler.push("first", 0, 0);
+        scheduler.push("second", 0, 0);
+
+        assert_eq!(scheduler.pop_startable().unwrap().job, "first");
+        assert!(scheduler.pop_startable().is_none());
+
+        scheduler.release(0);
+        assert_eq!(scheduler.pop_startable().unwrap().job, "second");
+    }
+
+    #[test]
+    fn test_scheduler_memory_budget() {
+        let mut scheduler = make_scheduler(4, 100);
+        scheduler.push("small", 0, 60);
+        scheduler.push("medium", 0, 50);
+        scheduler.push("huge", 0, 500);
+
+        assert_eq!(scheduler.pop_startable().unwrap().job, "small");
+        // The medium job does not fit with the small one.
+        assert!(scheduler.pop_startable().is_none());
+
+        scheduler.release(60);
+        assert_eq!(scheduler.pop_startable().unwrap().job, "medium");
+        assert!(scheduler.pop_startable().is_none());
+
+        // Jobs larger than the budget run alone.
+        scheduler.release(50);
+        assert_eq!(scheduler.pop_startable().unwrap().job, "huge");
+        assert_eq!(scheduler.used_memory, 500);
+    }
+
+    /// Creates a job whose memory file is named after the job and has `memory_size` bytes.
+    fn make_job(dir: &Path, name: &str, memory_size: usize, priority: i32) -> ProveJob {
+        let memory_file = dir.join(name);
+        std::fs::write(&memory_file, vec![0u8; memory_size]).unwrap();
+        let public_input = PublicInput {
+            layout: Layout::Plain,
+            rc_min: 0,
+            rc_max: 0,
+            n_steps: 1 << 10,
+            memory_segments: HashMap::new(),
+            public_memory: vec![],
+            dynamic_params: None,
+        };
+        let parameters = generate_prover_parameters(public_input.n_steps, Verifier::Stone);
+        ProveJob {
+            public_input,
+            private_input: AirPrivateInput(HashMap::new()),
+            memory: Artifact::OnDisk(memory_file),
+            trace: Artifact::InMemory(vec![]),
+            prover_config: ProverConfig::default(),
+            parameters,
+            priority,
+        }
+    }
+
+    /// Stub prover that logs the start and end of each job, named after its memory file,
+    /// then fails.
+    fn logging_command(log_file: PathBuf) -> Arc<ProverCommandFn> {
+        Arc::new(move |prover_working_dir: &ProverWorkingDirectory| {
+            let name = prover_working_dir._memory_file.file_name().unwrap();
+            let mut command = std::process::Command::new("sh");
+            command
+                .args([
+                    "-c",
+                    "echo \"start $0\" >> \"$1\"; sleep 0.1; echo \"end $0\" >> \"$1\"; exit 1",
+                ])
+                .arg(name)
+                .arg(&log_file);
+            command
+        })
+    }
+
+    async fn run_jobs(
+        config: ProverPoolConfig,
+        jobs: Vec<(&str, usize, i32)>,
+        check_pool: impl FnOnce(&ProverPool),
+    ) -> Vec<String> {
+        let dir = create_temp_dir().unwrap();
+        let log_file = dir.join("log.txt");
+        let (pool, mut results) =
+            ProverPool::with_command(config, logging_command(log_file.clone()));
+
+        let n_jobs = jobs.len();
+        for (name, memory_size, priority) in jobs {
+            pool.submit(make_job(&dir, name, memory_size, priority));
+        }
+        check_pool(&pool);
+        for _ in 0..n_jobs {
+            let job_result = results.recv().await.unwrap();
+            assert!(matches!(
+                job_result.result,
+                Err(ProverError::CommandError(_))
+            ));
+        }
+        assert_eq!(pool.running_jobs(), 0);
+
+        let log = std::fs::read_to_string(&log_file).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        log.lines().map(str::to_string).collect()
+    }
+
+    #[tokio::test]
+    async fn test_pool_priority_order() {
+        let config = ProverPoolConfig {
+            max_concurrent_jobs: 1,
+            memory_budget: u64::MAX,
+            memory_per_input_byte: 1,
+        };
+        let jobs = vec![("first", 1, 0), ("low", 1, 0), ("high", 1, 10)];
+
+        let log = run_jobs(config, jobs, |pool| {
+            assert_eq!(pool.running_jobs(), 1);
+            assert_eq!(pool.queued_jobs(), 2);
+        })
+        .await;
+
+        assert_eq!(
+            log,
+            vec![
+                "start first",
+                "end first",
+                "start high",
+                "end high",
+                "start low",
+                "end low"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pool_memory_budget() {
+        let config = ProverPoolConfig {
+            max_concurrent_jobs: 4,
+            memory_budget: 100,
+            memory_per_input_byte: 1,
+        };
+        let jobs = vec![("small", 60, 0), ("medium", 50, 0)];
+
+        let log = run_jobs(config, jobs, |pool| {
+            // The medium job does not fit with the small one.
+            assert_eq!(pool.running_jobs(), 1);
+            assert_eq!(pool.queued_jobs(), 1);
+        })
+        .await;
+
+        assert_eq!(
+            log,
+            vec!["start small", "end small", "start medium", "end medium"]
+        );
+    }
+
+    /// Check that the resources of a job are released if it panics.
+    #[tokio::test]
+    async fn test_pool_releases_panicking_job() {
+        let dir = create_temp_dir().unwrap();
+        let config = ProverPoolConfig {
+            max_concurrent_jobs: 1,
+            memory_budget: 100,
+            memory_per_input_byte: 1,
+        };
+        let (pool, mut results) = ProverPool::with_command(
+            config,
+            Arc::new(|_: &ProverWorkingDirectory| panic!("stub prover panicked")),
+        );
+
+        pool.submit(make_job(&dir, "job", 60, 0));
+        let job_result = results.recv().await.unwrap();
+
+        assert!(matches!(job_result.result, Err(ProverError::JobAborted(_))));
+        assert_eq!(pool.running_jobs(), 0);
+        assert_eq!(pool.state.scheduler.lock().unwrap().used_memory, 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,406 @@
+//! Client for the HTTP prover service, enabled with the `client` feature.
+//!
+//! The memory and trace files are sent in chunks with resumable uploads, so that large files
+//! do not need to be sent again from the start after a network failure.
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::time::Duration;
+
+use cairo_vm::air_private_input::{AirPrivateInput, AirPrivateInputSerializable};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::api::{ErrorResponse, JobCreatedResponse, JobId, JobStatus, UploadId, UploadStatus};
+use crate::models::{Artifact, Proof, ProverConfig, ProverParameters, PublicInput};
+
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration of a `RemoteProver`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteProverConfig {
+    /// URL of the prover service, ex: "http://prover:8080".
+    pub base_url: String,
+    /// Size of the upload chunks, in bytes.
+    pub chunk_size: usize,
+    /// Number of times a request is retried after a network or server error.
+    pub max_retries: u32,
+    /// Delay between two attempts of a request.
+    pub retry_delay: Duration,
+    /// Delay between two checks of the status of a job.
+    pub poll_interval: Duration,
+}
+
+impl RemoteProverConfig {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RemoteProverError {
+    #[error("request to the prover service failed")]
+    HttpError(#[from] reqwest::Error),
+    #[error("the prover service returned an error ({status}): {message}")]
+    ServerError { status: StatusCode, message: String },
+    #[error("the remote prover job failed: {0}")]
+    JobFailed(String),
+    #[error("failed to read an artifact")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Same as `api::UploadsJobRequest`, without taking ownership of the inputs.
+#[derive(Serialize)]
+struct UploadsJobRequestRef<'a> {
+    public_input: &'a PublicInput,
+    private_input: AirPrivateInputSerializable,
+    memory_upload_id: UploadId,
+    trace_upload_id: UploadId,
+    prover_config: &'a ProverConfig,
+    parameters: &'a ProverParameters,
+}
+
+fn artifact_len(artifact: &Artifact) -> Result<u64, std::io::Error> {
+    match artifact {
+        Artifact::InMemory(content) => Ok(content.len() as u64),
+        Artifact::OnDisk(path) => Ok(std::fs::metadata(path)?.len()),
+    }
+}
+
+async fn read_chunk(
+    artifact: &Artifact,
+    offset: u64,
+    len: usize,
+) -> Result<Vec<u8>, std::io::Error> {
+    match artifact {
+        Artifact::InMemory(content) => {
+            let start = offset as usize;
+            Ok(content[start..start + len].to_vec())
+        }
+        Artifact::OnDisk(path) => {
+            let mut file = tokio::fs::File::open(path).await?;
+            file.seek(SeekFrom::Start(offset)).await?;
+            let mut chunk = vec![0; len];
+            file.read_exact(&mut chunk).await?;
+            Ok(chunk)
+        }
+    }
+}
+
+/// Converts error responses into `RemoteProverError::ServerError`.
+async fn check_status(response: Response) -> Result<Response, RemoteProverError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let message = match response.json::<ErrorResponse>().await {
+        Ok(error_response) => error_response.error,
+        Err(_) => status.to_string(),
+    };
+    Err(RemoteProverError::ServerError { status, message })
+}
+
+/// Runs the prover on a remote machine, through the HTTP prover service.
+pub struct RemoteProver {
+    config: RemoteProverConfig,
+    client: reqwest::Client,
+}
+
+impl RemoteProver {
+    pub fn new(config: RemoteProverConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.base_url.trim_end_matches('/'), path)
+    }
+
+    /// Sends an idempotent request, retrying on network errors and server errors.
+    ///
+    /// Requests that create a resource must use `send_once`: if a response is lost, retrying
+    /// them would create the resource twice.
+    async fn send<F, Fut>(&self, send_request: F) -> Result<Response, RemoteProverError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Response, reqwest::Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = send_request().await;
+            let retryable = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(error) => error.is_connect() || error.is_timeout(),
+            };
+            if !retryable || attempt >= self.config.max_retries {
+                return check_status(result?).await;
+            }
+
+            attempt += 1;
+            tokio::time::sleep(self.config.retry_delay).await;
+        }
+    }
+
+    /// Sends a request without retrying it.
+    async fn send_once(&self, request: RequestBuilder) -> Result<Response, RemoteProverError> {
+        check_status(request.send().await?).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, RemoteProverError> {
+        let url = self.url(path);
+        let response = self.send(|| self.client.get(&url).send()).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Returns the number of bytes received by the server for an upload.
+    pub async fn upload_status(&self, upload_id: UploadId) -> Result<u64, RemoteProverError> {
+        let status: UploadStatus = self.get(&format!("/uploads/{upload_id}")).await?;
+        Ok(status.size)
+    }
+
+    /// Sends a chunk of an upload, starting at `offset`.
+    ///
+    /// Returns the number of bytes received by the server after this chunk.
+    pub async fn upload_chunk(
+        &self,
+        upload_id: UploadId,
+        offset: u64,
+        chunk: Vec<u8>,
+    ) -> Result<u64, RemoteProverError> {
+        let url = self.url(&format!("/uploads/{upload_id}/chunks/{offset}"));
+        let response = self
+            .send(|| self.client.put(&url).body(chunk.clone()).send())
+            .await?;
+        let status: UploadStatus = response.json().await?;
+        Ok(status.size)
+    }
+
+    /// Uploads an artifact to the prover service and returns the ID of the upload.
+    pub async fn upload(&self, artifact: &Artifact) -> Result<UploadId, RemoteProverError> {
+        let url = self.url("/uploads");
+        let response = self.send_once(self.client.post(&url)).await?;
+        let status: UploadStatus = response.json().await?;
+        self.resume_upload(status.upload_id, artifact).await?;
+        Ok(status.upload_id)
+    }
+
+    /// Sends the part of an artifact that the prover service has not received yet.
+    ///
+    /// Use this function to complete an upload interrupted by a failure.
+    pub async fn resume_upload(
+        &self,
+        upload_id: UploadId,
+        artifact: &Artifact,
+    ) -> Result<(), RemoteProverError> {
+        let len = artifact_len(artifact)?;
+        let mut offset = self.upload_status(upload_id).await?;
+
+        while offset < len {
+            let chunk_len = (len - offset).min(self.config.chunk_size as u64) as usize;
+            let chunk = read_chunk(artifact, offset, chunk_len).await?;
+            offset = match self.upload_chunk(upload_id, offset, chunk).await {
+                Ok(size) => size,
+                // A previous attempt may have been received despite the error, resync.
+                Err(RemoteProverError::ServerError {
+                    status: StatusCode::CONFLICT,
+                    ..
+                }) => self.upload_status(upload_id).await?,
+                Err(error) => return Err(error),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Returns the status of a job.
+    pub async fn job_status(&self, job_id: JobId) -> Result<JobStatus, RemoteProverError> {
+        self.get(&format!("/jobs/{job_id}")).await
+    }
+
+    /// Waits until a job is complete and returns its proof.
+    pub async fn wait_for_proof(&self, job_id: JobId) -> Result<Proof, RemoteProverError> {
+        loop {
+            match self.job_status(job_id).await? {
+                JobStatus::Succeeded => break,
+                JobStatus::Failed { error } => return Err(RemoteProverError::JobFailed(error)),
+                JobStatus::Executing | JobStatus::Proving => {
+                    tokio::time::sleep(self.config.poll_interval).await
+                }
+            }
+        }
+
+        self.get(&format!("/jobs/{job_id}/proof")).await
+    }
+
+    /// Run the Stone Prover on the specified program execution, on the prover service.
+    ///
+    /// * `public_input`: the public prover input generated by the Cairo program.
+    /// * `private_input`: the private prover input generated by the Cairo program.
+    /// * `memory`: the memory output of the Cairo program.
+    /// * `trace`: the execution trace of the Cairo program.
+    /// * `prover_config`: prover configuration.
+    /// * `parameters`: prover parameters for the Cairo program.
+    pub async fn run_prover(
+        &self,
+        public_input: &PublicInput,
+        private_input: &AirPrivateInput,
+        memory: &Artifact,
+        trace: &Artifact,
+        prover_config: &ProverConfig,
+        parameters: &ProverParameters,
+    ) -> Result<Proof, RemoteProverError> {
+        let memory_upload_id = self.upload(memory).await?;
+        let trace_upload_id = self.upload(trace).await?;
+
+        // The server replaces the memory and trace paths
+        let request = UploadsJobRequestRef {
+            public_input,
+            private_input: private_input.to_serializable(String::new(), String::new()),
+            memory_upload_id,
+            trace_upload_id,
+            prover_config,
+            parameters,
+        };
+        let url = self.url("/jobs/uploads");
+        let response = self
+            .send_once(self.client.post(&url).json(&request))
+            .await?;
+        let job: JobCreatedResponse = response.json().await?;
+
+        self.wait_for_proof(job.job_id).await
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use rstest::rstest;
+
+    use crate::server::{router, ServerConfig};
+    use crate::test_utils::{parsed_prover_test_case, ParsedProverTestCase};
+
+    use super::*;
+
+    /// Starts the prover service on a random local port and returns a client for it.
+    async fn start_server(chunk_size: usize) -> RemoteProver {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let router = router(ServerConfig::default()).unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        RemoteProver::new(RemoteProverConfig {
+            chunk_size,
+            ..RemoteProverConfig::new(format!("http://{address}"))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_resume_upload() {
+        let client = start_server(4).await;
+        let artifact = Artifact::InMemory((0..10).collect());
+
+        let upload_id = client.upload(&artifact).await.unwrap();
+        assert_eq!(client.upload_status(upload_id).await.unwrap(), 10);
+
+        // Simulate an upload interrupted after the first chunk
+        let url = client.url("/uploads");
+        let response = client.client.post(url).send().await.unwrap();
+        let upload: UploadStatus = response.json().await.unwrap();
+        client
+            .upload_chunk(upload.upload_id, 0, vec![0, 1, 2, 3])
+            .await
+            .unwrap();
+
+        client
+            .resume_upload(upload.upload_id, &artifact)
+            .await
+            .unwrap();
+        assert_eq!(client.upload_status(upload.upload_id).await.unwrap(), 10);
+    }
+
+    /// Starts a service that answers all requests with a server error and returns a client
+    /// for it, with the number of requests received by the service.
+    async fn start_failing_server() -> (RemoteProver, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let n_requests = Arc::new(AtomicUsize::new(0));
+        let counter = n_requests.clone();
+        let router = axum::Router::new().fallback(move || async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        });
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = RemoteProver::new(RemoteProverConfig {
+            max_retries: 2,
+            retry_delay: Duration::ZERO,
+            ..RemoteProverConfig::new(format!("http://{address}"))
+        });
+        (client, n_requests)
+    }
+
+    /// Check that only the requests that do not create resources are retried.
+    #[tokio::test]
+    async fn test_retry_idempotent_requests() {
+        let (client, n_requests) = start_failing_server().await;
+
+        assert!(client.upload_status(0).await.is_err());
+        assert_eq!(n_requests.swap(0, Ordering::SeqCst), 3);
+
+        assert!(client.upload_chunk(0, 0, vec![0, 1, 2]).await.is_err());
+        assert_eq!(n_requests.swap(0, Ordering::SeqCst), 3);
+
+        assert!(client.upload(&Artifact::InMemory(vec![0])).await.is_err());
+        assert_eq!(n_requests.swap(0, Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_job() {
+        let client = start_server(DEFAULT_CHUNK_SIZE).await;
+        let result = client.job_status(42).await;
+        assert!(matches!(
+            result,
+            Err(RemoteProverError::ServerError {
+                status: StatusCode::NOT_FOUND,
+                ..
+            })
+        ));
+    }
+
+    /// Check that a proof can be generated through the prover service.
+    #[rstest]
+    #[tokio::test]
+    async fn test_run_prover_remote(parsed_prover_test_case: ParsedProverTestCase) {
+        let client = start_server(1024).await;
+        let proof = client
+            .run_prover(
+                &parsed_prover_test_case.public_input,
+                &parsed_prover_test_case.private_input,
+                &Artifact::InMemory(parsed_prover_test_case.memory),
+                &Artifact::InMemory(parsed_prover_test_case.trace),
+                &parsed_prover_test_case.prover_config,
+                &parsed_prover_test_case.prover_parameters,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(proof.proof_hex, parsed_prover_test_case.proof.proof_hex);
+    }
+}
@@ -0,0 +1,765 @@
+//! HTTP service exposing the prover and verifier, enabled with the `server` feature.
+//!
+//! Routes:
+//! * `POST /jobs/program`: run a compiled Cairo program and prove its execution.
+//! * `POST /jobs/artifacts`: prove an execution from its artifacts.
+//! * `GET /jobs/:job_id`: get the status of a job.
+//! * `GET /jobs/:job_id/proof`: get the proof generated by a job.
+//! * `POST /verify`: verify a proof.
+//! * `POST /uploads`: start a resumable upload of a memory or trace file.
+//! * `GET /uploads/:upload_id`: get the number of bytes received for an upload.
+//! * `PUT /uploads/:upload_id/chunks/:offset`: append a chunk to an upload.
+//! * `POST /jobs/uploads`: prove an execution from uploaded artifacts.
+//!
+//! The request and response types are defined in the `api` module.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::api::{
+    ArtifactsJobRequest, ErrorResponse, JobCreatedResponse, JobId, JobStatus, ProgramJobRequest,
+    UploadId, UploadStatus, UploadsJobRequest, VerifyResponse,
+};
+use crate::cairo_vm::{extract_execution_artifacts, run_in_proof_mode, ExecutionArtifacts};
+use crate::error::VerifierError;
+use crate::fri::{generate_prover_parameters_for_public_input, FriError};
+use crate::models::{Artifact, Proof, ProverConfig, ProverParameters, Verifier};
+use crate::prover::create_temp_dir;
+use crate::prover_pool::{
+    JobId as PoolJobId, ProveJob, ProveJobResult, ProverPool, ProverPoolConfig,
+};
+use crate::verifier::verify_proof_async;
+
+/// Maximum size of a request body, which bounds the size of upload chunks.
+const MAX_BODY_SIZE: usize = 64 * 1024 * 1024;
+
+/// Default time during which the status and proof of a completed job are kept.
+const DEFAULT_JOB_TTL: Duration = Duration::from_secs(60 * 60);
+/// Default time after which an upload that receives no chunk is abandoned.
+const DEFAULT_UPLOAD_TTL: Duration = Duration::from_secs(60 * 60);
+/// Maximum time between two removals of expired jobs and uploads.
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Configuration of the service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Resources available to the prover.
+    pub pool: ProverPoolConfig,
+    /// Time during which the status and proof of a completed job are kept. Jobs are removed
+    /// once this time has elapsed, whether their proof was downloaded or not.
+    pub job_ttl: Duration,
+    /// Time after which an upload that receives no chunk is abandoned and its file deleted.
+    pub upload_ttl: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            pool: ProverPoolConfig::default(),
+            job_ttl: DEFAULT_JOB_TTL,
+            upload_ttl: DEFAULT_UPLOAD_TTL,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ServerError {
+    #[error("unknown job: {0}")]
+    UnknownJob(JobId),
+    #[error("job {0} has no proof: {1:?}")]
+    ProofNotAvailable(JobId, JobStatus),
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("unknown upload: {0}")]
+    UnknownUpload(UploadId),
+    #[error("unexpected chunk offset for upload {upload_id}, expected {expected}")]
+    UnexpectedOffset { upload_id: UploadId, expected: u64 },
+    #[error("failed to store the upload")]
+    IoError(#[from] std::io::Error),
+    #[error("failed to run the verifier")]
+    VerifierError(#[source] VerifierError),
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            Self::UnknownJob(_) | Self::UnknownUpload(_) => StatusCode::NOT_FOUND,
+            Self::ProofNotAvailable(..) | Self::UnexpectedOffset { .. } => StatusCode::CONFLICT,
+            Self::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            Self::IoError(_) | Self::VerifierError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = ErrorResponse {
+            error: self.to_string(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug)]
+struct Job {
+    status: JobStatus,
+    proof: Option<Proof>,
+    /// Uploaded files used by the job, deleted once the job is complete.
+    uploads: Vec<PathBuf>,
+    completed_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    jobs: HashMap<JobId, Job>,
+    next_job_id: JobId,
+    /// Maps the IDs of the prover pool to the IDs of the server.
+    pool_jobs: HashMap<PoolJobId, JobId>,
+}
+
+impl Jobs {
+    fn create(&mut self, status: JobStatus) -> JobId {
+        let job_id = self.next_job_id;
+        self.next_job_id += 1;
+        self.jobs.insert(
+            job_id,
+            Job {
+                status,
+                proof: None,
+                uploads: vec![],
+                completed_at: None,
+            },
+        );
+        job_id
+    }
+
+    fn complete(&mut self, job_id: JobId, status: JobStatus, proof: Option<Proof>) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.status = status;
+            job.proof = proof;
+            job.completed_at = Some(Instant::now());
+        }
+    }
+
+    fn fail(&mut self, job_id: JobId, error: String) {
+        self.complete(job_id, JobStatus::Failed { error }, None);
+    }
+
+    /// Removes the jobs completed for longer than `ttl`.
+    fn evict_expired(&mut self, now: Instant, ttl: Duration) {
+        self.jobs.retain(|_, job| {
+            job.completed_at
+                .is_none_or(|completed_at| now.duration_since(completed_at) < ttl)
+        });
+    }
+}
+
+#[derive(Debug)]
+struct Upload {
+    file: PathBuf,
+    updated_at: Instant,
+}
+
+/// Upload shared between requests. The lock serializes the chunks of an upload.
+type SharedUpload = Arc<tokio::sync::Mutex<Upload>>;
+
+#[derive(Default)]
+struct Uploads {
+    uploads: HashMap<UploadId, SharedUpload>,
+    next_upload_id: UploadId,
+}
+
+impl Uploads {
+    /// Removes the uploads that did not receive a chunk for `ttl` and returns their files.
+    fn evict_expired(&mut self, now: Instant, ttl: Duration) -> Vec<PathBuf> {
+        let mut expired_files = vec![];
+        self.uploads.retain(|_, upload| {
+            // An upload that is being written to is not abandoned.
+            let Ok(upload) = upload.try_lock() else {
+                return true;
+            };
+            let expired = now.duration_since(upload.updated_at) >= ttl;
+            if expired {
+                expired_files.push(upload.file.clone());
+            }
+            !expired
+        });
+        expired_files
+    }
+}
+
+struct AppState {
+    pool: ProverPool,
+    jobs: Mutex<Jobs>,
+    uploads: Mutex<Uploads>,
+    upload_dir: PathBuf,
+    job_ttl: Duration,
+    upload_ttl: Duration,
+}
+
+type SharedState = Arc<AppState>;
+
+impl AppState {
+    fn new(pool: ProverPool, upload_dir: PathBuf, config: &ServerConfig) -> Self {
+        Self {
+            pool,
+            jobs: Mutex::new(Jobs::default()),
+            uploads: Mutex::new(Uploads::default()),
+            upload_dir,
+            job_ttl: config.job_ttl,
+            upload_ttl: config.upload_ttl,
+        }
+    }
+
+    fn upload(&self, upload_id: UploadId) -> Result<SharedUpload, ServerError> {
+        let uploads = self.uploads.lock().unwrap();
+        uploads
+            .uploads
+            .get(&upload_id)
+            .cloned()
+            .ok_or(ServerError::UnknownUpload(upload_id))
+    }
+
+    /// Submits a job to the prover pool.
+    fn submit(&self, job_id: JobId, prove_job: ProveJob) {
+        // Keep the lock while submitting so that the result cannot be received before
+        // the job ID is registered.
+        let mut jobs = self.jobs.lock().unwrap();
+        let pool_job_id = self.pool.submit(prove_job);
+        jobs.pool_jobs.insert(pool_job_id, job_id);
+        if let Some(job) = jobs.jobs.get_mut(&job_id) {
+            job.status = JobStatus::Proving;
+        }
+    }
+}
+
+fn parse_verifier(verifier: Option<&str>) -> Result<Verifier, ServerError> {
+    verifier
+        .map(Verifier::from_str)
+        .unwrap_or(Ok(Verifier::Stone))
+        .map_err(ServerError::InvalidRequest)
+}
+
+fn make_prove_job(
+    artifacts: ExecutionArtifacts,
+    prover_config: Option<ProverConfig>,
+    parameters: Option<ProverParameters>,
+    verifier: Verifier,
+) -> Result<ProveJob, FriError> {
+    let parameters = match parameters {
+        Some(parameters) => parameters,
+        None => generate_prover_parameters_for_public_input(&artifacts.public_input, verifier)?,
+    };
+    Ok(ProveJob {
+        public_input: artifacts.public_input,
+        private_input: artifacts.private_input,
+        memory: artifacts.memory.into(),
+        trace: artifacts.trace.into(),
+        prover_config: prover_config.unwrap_or_default(),
+        parameters,
+        priority: 0,
+    })
+}
+
+async fn create_program_job(
+    State(state): State<SharedState>,
+    Json(request): Json<ProgramJobRequest>,
+) -> Result<Json<JobCreatedResponse>, ServerError> {
+    let verifier = parse_verifier(request.verifier.as_deref())?;
+    let program_content = serde_json::to_vec(&request.program)
+        .map_err(|e| ServerError::InvalidRequest(e.to_string()))?;
+    let job_id = state.jobs.lock().unwrap().create(JobStatus::Executing);
+
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        let layout = request.layout;
+        let allow_missing_builtins = request.allow_missing_builtins;
+        let execution = tokio::task::spawn_blocking(move || {
+            let (cairo_runner, vm) =
+                run_in_proof_mode(&program_content, layout, allow_missing_builtins)
+                    .map_err(|e| e.to_string())?;
+            extract_execution_artifacts(cairo_runner, vm).map_err(|e| e.to_string())
+        })
+        .await;
+
+        match execution {
+            Ok(Ok(artifacts)) => match make_prove_job(
+                artifacts,
+                request.prover_config,
+                request.parameters,
+                verifier,
+            ) {
+                Ok(prove_job) => task_state.submit(job_id, prove_job),
+                Err(error) => task_state
+                    .jobs
+                    .lock()
+                    .unwrap()
+                    .fail(job_id, error.to_string()),
+            },
+            Ok(Err(error)) => task_state.jobs.lock().unwrap().fail(job_id, error),
+            Err(error) => task_state
+                .jobs
+                .lock()
+                .unwrap()
+                .fail(job_id, error.to_string()),
+        }
+    });
+
+    Ok(Json(JobCreatedResponse { job_id }))
+}
+
+async fn create_artifacts_job(
+    State(state): State<SharedState>,
+    Json(request): Json<ArtifactsJobRequest>,
+) -> Result<Json<JobCreatedResponse>, ServerError> {
+    let verifier = parse_verifier(request.verifier.as_deref())?;
+    let decode = |content: &str, name: &str| {
+        BASE64
+            .decode(content)
+            .map_err(|e| ServerError::InvalidRequest(format!("invalid {name}: {e}")))
+    };
+    let memory = decode(&request.memory, "memory")?;
+    let trace = decode(&request.trace, "trace")?;
+
+    let parameters = match request.parameters {
+        Some(parameters) => parameters,
+        None => generate_prover_parameters_for_public_input(&request.public_input, verifier)
+            .map_err(|e| ServerError::InvalidRequest(e.to_string()))?,
+    };
+    let prove_job = ProveJob {
+        public_input: request.public_input,
+        private_input: request.private_input.into(),
+        memory: Artifact::InMemory(memory),
+        trace: Artifact::InMemory(trace),
+        prover_config: request.prover_config.unwrap_or_default(),
+        parameters,
+        priority: 0,
+    };
+
+    let job_id = state.jobs.lock().unwrap().create(JobStatus::Proving);
+    state.submit(job_id, prove_job);
+    Ok(Json(JobCreatedResponse { job_id }))
+}
+
+async fn create_upload(
+    State(state): State<SharedState>,
+) -> Result<Json<UploadStatus>, ServerError> {
+    let upload_id = {
+        let mut uploads = state.uploads.lock().unwrap();
+        let upload_id = uploads.next_upload_id;
+        uploads.next_upload_id += 1;
+        upload_id
+    };
+
+    let upload_file = state.upload_dir.join(format!("{upload_id}.bin"));
+    tokio::fs::File::create(&upload_file).await?;
+    let upload = Upload {
+        file: upload_file,
+        updated_at: Instant::now(),
+    };
+    state
+        .uploads
+        .lock()
+        .unwrap()
+        .uploads
+        .insert(upload_id, Arc::new(tokio::sync::Mutex::new(upload)));
+    Ok(Json(UploadStatus { upload_id, size: 0 }))
+}
+
+async fn get_upload_status(
+    State(state): State<SharedState>,
+    Path(upload_id): Path<UploadId>,
+) -> Result<Json<UploadStatus>, ServerError> {
+    let upload = state.upload(upload_id)?;
+    let upload = upload.lock().await;
+    let size = tokio::fs::metadata(&upload.file).await?.len();
+    Ok(Json(UploadStatus { upload_id, size }))
+}
+
+async fn upload_chunk(
+    State(state): State<SharedState>,
+    Path((upload_id, offset)): Path<(UploadId, u64)>,
+    chunk: Bytes,
+) -> Result<Json<UploadStatus>, ServerError> {
+    let upload = state.upload(upload_id)?;
+    // Hold the lock until the chunk is written, concurrent chunks would pass the offset
+    // check at the same time.
+    let mut upload = upload.lock().await;
+    let mut file = tokio::fs::OpenOptions::new()
+        .append(true)
+        .open(&upload.file)
+        .await?;
+
+    // Chunks must be sent in order. The client resumes from the returned offset on error.
+    let size = file.metadata().await?.len();
+    if offset != size {
+        return Err(ServerError::UnexpectedOffset {
+            upload_id,
+            expected: size,
+        });
+    }
+    file.write_all(&chunk).await?;
+    file.flush().await?;
+    upload.updated_at = Instant::now();
+
+    Ok(Json(UploadStatus {
+        upload_id,
+        size: size + chunk.len() as u64,
+    }))
+}
+
+async fn create_uploads_job(
+    State(state): State<SharedState>,
+    Json(request): Json<UploadsJobRequest>,
+) -> Result<Json<JobCreatedResponse>, ServerError> {
+    if request.memory_upload_id == request.trace_upload_id {
+        return Err(ServerError::InvalidRequest(
+            "the memory and trace must be sent in different uploads".to_string(),
+        ));
+    }
+
+    // The uploads are consumed by the job.
+    let (memory_upload, trace_upload) = {
+        let mut uploads = state.uploads.lock().unwrap();
+        for upload_id in [request.memory_upload_id, request.trace_upload_id] {
+            if !uploads.uploads.contains_key(&upload_id) {
+                return Err(ServerError::UnknownUpload(upload_id));
+            }
+        }
+        (
+            uploads.uploads.remove(&request.memory_upload_id).unwrap(),
+            uploads.uploads.remove(&request.trace_upload_id).unwrap(),
+        )
+    };
+    // Wait for the chunks being written.
+    let memory_file = memory_upload.lock().await.file.clone();
+    let trace_file = trace_upload.lock().await.file.clone();
+
+    let prove_job = ProveJob {
+        public_input: request.public_input,
+        private_input: request.private_input.into(),
+        memory: Artifact::OnDisk(memory_file.clone()),
+        trace: Artifact::OnDisk(trace_file.clone()),
+        prover_config: request.prover_config,
+        parameters: request.parameters,
+        priority: 0,
+    };
+
+    let job_id = {
+        let mut jobs = state.jobs.lock().unwrap();
+        let job_id = jobs.create(JobStatus::Proving);
+        if let Some(job) = jobs.jobs.get_mut(&job_id) {
+            job.uploads = vec![memory_file, trace_file];
+        }
+        job_id
+    };
+    state.submit(job_id, prove_job);
+    Ok(Json(JobCreatedResponse { job_id }))
+}
+
+async fn get_job_status(
+    State(state): State<SharedState>,
+    Path(job_id): Path<JobId>,
+) -> Result<Json<JobStatus>, ServerError> {
+    let jobs = state.jobs.lock().unwrap();
+    let job = jobs
+        .jobs
+        .get(&job_id)
+        .ok_or(ServerError::UnknownJob(job_id))?;
+    Ok(Json(job.status.clone()))
+}
+
+async fn get_job_proof(
+    State(state): State<SharedState>,
+    Path(job_id): Path<JobId>,
+) -> Result<Response, ServerError> {
+    let jobs = state.jobs.lock().unwrap();
+    let job = jobs
+        .jobs
+        .get(&job_id)
+        .ok_or(ServerError::UnknownJob(job_id))?;
+    match &job.proof {
+        Some(proof) => Ok(Json(proof).into_response()),
+        None => Err(ServerError::ProofNotAvailable(job_id, job.status.clone())),
+    }
+}
+
+async fn verify(Json(proof): Json<Proof>) -> Result<Json<VerifyResponse>, ServerError> {
+    let response = match verify_proof_async(&proof).await {
+        Ok(()) => VerifyResponse {
+            valid: true,
+            error: None,
+        },
+        // Only a verifier run that fails means that the proof is invalid.
+        Err(error @ VerifierError::CommandError(_)) => VerifyResponse {
+            valid: false,
+            error: Some(error.to_string()),
+        },
+        Err(error) => return Err(ServerError::VerifierError(error)),
+    };
+    Ok(Json(response))
+}
+
+/// Records the results of the prover pool in the job table.
+async fn collect_results(state: SharedState, mut results: UnboundedReceiver<ProveJobResult>) {
+    while let Some(ProveJobResult {
+        job_id: pool_job_id,
+        result,
+    }) = results.recv().await
+    {
+        let mut jobs = state.jobs.lock().unwrap();
+        let Some(job_id) = jobs.pool_jobs.remove(&pool_job_id) else {
+            continue;
+        };
+        match result {
+            Ok(proof) => jobs.complete(job_id, JobStatus::Succeeded, Some(proof)),
+            Err(error) => jobs.fail(job_id, error.to_string()),
+        }
+        if let Some(job) = jobs.jobs.get_mut(&job_id) {
+            for upload in job.uploads.drain(..) {
+                // A leftover file is not worth failing the job for, ignore errors.
+                let _ = std::fs::remove_file(upload);
+            }
+        }
+    }
+}
+
+/// Periodically removes the expired jobs and the abandoned uploads.
+async fn sweep_expired(state: SharedState) {
+    let period = state
+        .job_ttl
+        .min(state.upload_ttl)
+        .clamp(Duration::from_secs(1), MAX_SWEEP_INTERVAL);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        let now = Instant::now();
+        state.jobs.lock().unwrap().evict_expired(now, state.job_ttl);
+        let expired_files = state
+            .uploads
+            .lock()
+            .unwrap()
+            .evict_expired(now, state.upload_ttl);
+        for file in expired_files {
+            // A leftover file is not worth stopping the sweep for, ignore errors.
+            let _ = tokio::fs::remove_file(file).await;
+        }
+    }
+}
+
+/// Creates the router of the service. Must be called from a Tokio runtime.
+///
+/// Uploaded files are stored in a temporary directory.
+///
+/// * `config`: Configuration of the service.
+pub fn router(config: ServerConfig) -> std::io::Result<Router> {
+    let (pool, results) = ProverPool::new(config.pool.clone());
+    let state = Arc::new(AppState::new(pool, create_temp_dir()?, &config));
+    tokio::spawn(collect_results(state.clone(), results));
+    tokio::spawn(sweep_expired(state.clone()));
+
+    let router = Router::new()
+        .route("/jobs/program", post(create_program_job))
+        .route("/jobs/artifacts", post(create_artifacts_job))
+        .route("/jobs/:job_id", get(get_job_status))
+        .route("/jobs/:job_id/proof", get(get_job_proof))
+        .route("/jobs/uploads", post(create_uploads_job))
+        .route("/verify", post(verify))
+        .route("/uploads", post(create_upload))
+        .route("/uploads/:upload_id", get(get_upload_status))
+        .route("/uploads/:upload_id/chunks/:offset", put(upload_chunk))
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+        .with_state(state);
+    Ok(router)
+}
+
+/// Runs the service until the process is stopped.
+///
+/// * `address`: Address to listen on.
+/// * `config`: Configuration of the service.
+pub async fn serve(address: SocketAddr, config: ServerConfig) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    axum::serve(listener, router(config)?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cairo_vm::air_private_input::AirPrivateInput;
+    use serde_json::json;
+
+    use crate::fri::generate_prover_parameters;
+    use crate::models::{Layout, PublicInput};
+
+    use super::*;
+
+    fn make_state() -> SharedState {
+        let (pool, _) = ProverPool::new(ProverPoolConfig::default());
+        Arc::new(AppState::new(
+            pool,
+            create_temp_dir().unwrap(),
+            &ServerConfig::default(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_get_job_status() {
+        let state = make_state();
+        let job_id = state.jobs.lock().unwrap().create(JobStatus::Executing);
+
+        let Json(status) = get_job_status(State(state.clone()), Path(job_id))
+            .await
+            .unwrap();
+        assert_eq!(status, JobStatus::Executing);
+
+        let result = get_job_proof(State(state.clone()), Path(job_id)).await;
+        assert!(matches!(result, Err(ServerError::ProofNotAvailable(..))));
+
+        let result = get_job_status(State(state.clone()), Path(job_id + 1)).await;
+        assert!(matches!(result, Err(ServerError::UnknownJob(_))));
+        std::fs::remove_dir_all(&state.upload_dir).unwrap();
+    }
+
+    #[test]
+    fn test_evict_expired_jobs() {
+        let ttl = Duration::from_secs(60);
+        let mut jobs = Jobs::default();
+        let running_job_id = jobs.create(JobStatus::Proving);
+        let failed_job_id = jobs.create(JobStatus::Proving);
+        jobs.fail(failed_job_id, "prover run failed".to_string());
+        let completed_at = jobs.jobs[&failed_job_id].completed_at.unwrap();
+
+        jobs.evict_expired(completed_at + ttl / 2, ttl);
+        assert!(jobs.jobs.contains_key(&failed_job_id));
+
+        jobs.evict_expired(completed_at + ttl, ttl);
+        assert!(!jobs.jobs.contains_key(&failed_job_id));
+        // Jobs in progress are never evicted.
+        assert!(jobs.jobs.contains_key(&running_job_id));
+    }
+
+    #[tokio::test]
+    async fn test_upload_chunks() {
+        let state = make_state();
+        let Json(upload) = create_upload(State(state.clone())).await.unwrap();
+        let upload_id = upload.upload_id;
+
+        let chunk = Bytes::from_static(b"abc");
+        let Json(upload) = upload_chunk(State(state.clone()), Path((upload_id, 0)), chunk)
+            .await
+            .unwrap();
+        assert_eq!(upload.size, 3);
+
+        // Chunks must be sent in order
+        let chunk = Bytes::from_static(b"def");
+        let result = upload_chunk(State(state.clone()), Path((upload_id, 0)), chunk).await;
+        assert!(matches!(
+            result,
+            Err(ServerError::UnexpectedOffset { expected: 3, .. })
+        ));
+
+        let chunk = Bytes::from_static(b"def");
+        let Json(upload) = upload_chunk(State(state.clone()), Path((upload_id, 3)), chunk)
+            .await
+            .unwrap();
+        assert_eq!(upload.size, 6);
+        let upload_file = state.upload(upload_id).unwrap().lock().await.file.clone();
+        assert_eq!(std::fs::read(upload_file).unwrap(), b"abcdef");
+        std::fs::remove_dir_all(&state.upload_dir).unwrap();
+    }
+
+    /// Check that concurrent chunks with the same offset are not both written.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_upload_concurrent_chunks() {
+        let state = make_state();
+        let Json(upload) = create_upload(State(state.clone())).await.unwrap();
+        let upload_id = upload.upload_id;
+
+        let send_chunk = |content: &'static [u8]| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                upload_chunk(
+                    State(state),
+                    Path((upload_id, 0)),
+                    Bytes::from_static(content),
+                )
+                .await
+            })
+        };
+        let (first, second) = tokio::join!(send_chunk(b"abc"), send_chunk(b"def"));
+
+        let results = [first.unwrap(), second.unwrap()];
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        let upload_file = state.upload(upload_id).unwrap().lock().await.file.clone();
+        assert_eq!(std::fs::read(upload_file).unwrap().len(), 3);
+        std::fs::remove_dir_all(&state.upload_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_evict_abandoned_uploads() {
+        let state = make_state();
+        let ttl = Duration::from_secs(60);
+        let Json(upload) = create_upload(State(state.clone())).await.unwrap();
+        let upload_id = upload.upload_id;
+        let updated_at = state.upload(upload_id).unwrap().lock().await.updated_at;
+
+        let mut uploads = state.uploads.lock().unwrap();
+        assert!(uploads.evict_expired(updated_at + ttl / 2, ttl).is_empty());
+        let expired_files = uploads.evict_expired(updated_at + ttl, ttl);
+        assert_eq!(expired_files, vec![state.upload_dir.join("0.bin")]);
+        assert!(!uploads.uploads.contains_key(&upload_id));
+        drop(uploads);
+        std::fs::remove_dir_all(&state.upload_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_uploads_job_with_the_same_upload() {
+        let state = make_state();
+        let Json(upload) = create_upload(State(state.clone())).await.unwrap();
+        let request = UploadsJobRequest {
+            public_input: PublicInput {
+                layout: Layout::Recursive,
+                rc_min: 0,
+                rc_max: 0,
+                n_steps: 1024,
+                memory_segments: HashMap::new(),
+                public_memory: vec![],
+                dynamic_params: None,
+            },
+            private_input: AirPrivateInput(HashMap::new())
+                .to_serializable(String::new(), String::new()),
+            memory_upload_id: upload.upload_id,
+            trace_upload_id: upload.upload_id,
+            prover_config: ProverConfig::default(),
+            parameters: generate_prover_parameters(1024, Verifier::Stone),
+        };
+
+        let result = create_uploads_job(State(state.clone()), Json(request)).await;
+        assert!(matches!(result, Err(ServerError::InvalidRequest(_))));
+        // The upload is not consumed.
+        assert!(state.upload(upload.upload_id).is_ok());
+        std::fs::remove_dir_all(&state.upload_dir).unwrap();
+    }
+
+    #[test]
+    fn test_job_status_serialization() {
+        let status = JobStatus::Failed {
+            error: "prover run failed".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            json!({"status": "failed", "error": "prover run failed"})
+        );
+    }
+}
@@ -18,7 +18,6 @@ pub fn read_proof_file<P: AsRef<Path>>(proof_file: P) -> Proof {
 
 /// All the files forming a complete prover test case.
 pub struct ProverTestCase {
-    pub program_file: PathBuf,
     pub compiled_program_file: PathBuf,
     pub public_input_file: PathBuf,
     pub private_input_file: PathBuf,
@@ -44,7 +43,6 @@ pub fn load_test_case_file(filename: &str) -> String {
 
 #[fixture]
 pub fn prover_test_case(#[default("fibonacci")] test_case: String) -> ProverTestCase {
-    let program_file = get_test_case_file_path(&format!("{test_case}/{test_case}.cairo"));
     let compiled_program_file = get_test_case_file_path(&format!("{test_case}/fibonacci.json"));
     let public_input_file = get_test_case_file_path(&format!("{test_case}/air_public_input.json"));
     let private_input_file =
@@ -58,7 +56,6 @@ pub fn prover_test_case(#[default("fibonacci")] test_case: String) -> ProverTest
     let proof_file = get_test_case_file_path(&format!("{test_case}/proof.json"));
 
     ProverTestCase {
-        program_file,
         compiled_program_file,
         public_input_file,
         private_input_file,
@@ -1,7 +1,11 @@
-use std::path::Path;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
 
 use crate::error::VerifierError;
-use crate::models::ProofAnnotations;
+use crate::json::write_json_to_file;
+use crate::models::{Proof, ProofAnnotations, VerifierAnnotations};
 
 /// Run the Stone Verifier on the specified program execution, asynchronously.
 ///
@@ -45,19 +49,36 @@ pub fn run_verifier_from_command_line(
     extra_output_file: Option<&Path>,
 ) -> Result<(), VerifierError> {
     let mut command = std::process::Command::new("cpu_air_verifier");
-    command
-        .arg("cpu_air_verifier")
-        .arg("--in_file")
-        .arg(in_file);
+    command.args(verifier_args(in_file, annotation_file, extra_output_file));
+    run_verifier_command(command)
+}
+
+/// Returns the command-line arguments of `cpu_air_verifier`.
+pub(crate) fn verifier_args(
+    in_file: &Path,
+    annotation_file: Option<&Path>,
+    extra_output_file: Option<&Path>,
+) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec![
+        "cpu_air_verifier".into(),
+        "--in_file".into(),
+        in_file.into(),
+    ];
 
     if let Some(annotation_file) = annotation_file {
-        command.arg("--annotation_file").arg(annotation_file);
+        args.extend(["--annotation_file".into(), annotation_file.into()]);
     }
 
     if let Some(extra_output_file) = extra_output_file {
-        command.arg("--extra_output_file").arg(extra_output_file);
+        args.extend(["--extra_output_file".into(), extra_output_file.into()]);
     }
 
+    args
+}
+
+pub(crate) fn run_verifier_command(
+    mut command: std::process::Command,
+) -> Result<(), VerifierError> {
     let output = command.output()?;
 
     if !output.status.success() {
@@ -67,6 +88,18 @@ pub fn run_verifier_from_command_line(
     Ok(())
 }
 
+pub(crate) async fn run_verifier_command_async(
+    command: std::process::Command,
+) -> Result<(), VerifierError> {
+    let output = tokio::process::Command::from(command).output().await?;
+
+    if !output.status.success() {
+        return Err(VerifierError::CommandError(output));
+    }
+
+    Ok(())
+}
+
 /// Run the Stone Verifier on the specified program execution, asynchronously.
 ///
 /// The main difference from the synchronous implementation is that the verifier process
@@ -118,34 +151,100 @@ pub async fn run_verifier_from_command_line_async(
     annotation_file: Option<&Path>,
     extra_output_file: Option<&Path>,
 ) -> Result<(), VerifierError> {
-    let mut command = tokio::process::Command::new("cpu_air_verifier");
-    command
-        .arg("cpu_air_verifier")
-        .arg("--in_file")
-        .arg(in_file);
+    let mut command = std::process::Command::new("cpu_air_verifier");
+    command.args(verifier_args(in_file, annotation_file, extra_output_file));
+    run_verifier_command_async(command).await
+}
 
-    if let Some(annotation_file) = annotation_file {
-        command.arg("--annotation_file").arg(annotation_file);
-    }
+/// Files used to run the verifier on an in-memory proof. Deleted when dropped.
+struct VerifierFiles {
+    _dir: TempDir,
+    proof_file: PathBuf,
+    annotation_file: PathBuf,
+    extra_output_file: PathBuf,
+}
 
-    if let Some(extra_output_file) = extra_output_file {
-        command.arg("--extra_output_file").arg(extra_output_file);
-    }
+fn prepare_verifier_files(proof: &Proof) -> Result<VerifierFiles, std::io::Error> {
+    let dir = tempfile::tempdir()?;
+    let proof_file = dir.path().join("proof.json");
+    write_json_to_file(proof, &proof_file)?;
+
+    Ok(VerifierFiles {
+        proof_file,
+        annotation_file: dir.path().join("annotations.txt"),
+        extra_output_file: dir.path().join("extra_annotations.txt"),
+        _dir: dir,
+    })
+}
 
-    let output = command.output().await?;
+fn read_annotations(files: &VerifierFiles) -> Result<VerifierAnnotations, std::io::Error> {
+    let read_lines = |path: &Path| -> Result<Vec<String>, std::io::Error> {
+        Ok(std::fs::read_to_string(path)?
+            .lines()
+            .map(|line| line.to_string())
+            .collect())
+    };
 
-    if !output.status.success() {
-        return Err(VerifierError::CommandError(output));
-    }
+    Ok(VerifierAnnotations {
+        annotations: read_lines(&files.annotation_file)?,
+        extra_annotations: read_lines(&files.extra_output_file)?,
+    })
+}
 
-    Ok(())
+/// Verify a proof with the Stone Verifier.
+///
+/// The proof is written to a temporary file, deleted once the verifier exits.
+///
+/// * `proof`: Proof generated by the prover.
+pub fn verify_proof(proof: &Proof) -> Result<(), VerifierError> {
+    let files = prepare_verifier_files(proof)?;
+    run_verifier(&files.proof_file)
+}
+
+/// Verify a proof with the Stone Verifier and return the annotations generated by
+/// the verifier.
+///
+/// * `proof`: Proof generated by the prover.
+pub fn verify_proof_with_annotations(proof: &Proof) -> Result<VerifierAnnotations, VerifierError> {
+    let files = prepare_verifier_files(proof)?;
+    run_verifier_with_annotations(
+        &files.proof_file,
+        &files.annotation_file,
+        &files.extra_output_file,
+    )?;
+    Ok(read_annotations(&files)?)
+}
+
+/// Verify a proof with the Stone Verifier, asynchronously.
+///
+/// * `proof`: Proof generated by the prover.
+pub async fn verify_proof_async(proof: &Proof) -> Result<(), VerifierError> {
+    let files = prepare_verifier_files(proof)?;
+    run_verifier_async(&files.proof_file).await
+}
+
+/// Verify a proof with the Stone Verifier asynchronously and return the annotations generated
+/// by the verifier.
+///
+/// * `proof`: Proof generated by the prover.
+pub async fn verify_proof_with_annotations_async(
+    proof: &Proof,
+) -> Result<VerifierAnnotations, VerifierError> {
+    let files = prepare_verifier_files(proof)?;
+    run_verifier_with_annotations_async(
+        &files.proof_file,
+        &files.annotation_file,
+        &files.extra_output_file,
+    )
+    .await?;
+    Ok(read_annotations(&files)?)
 }
 
 #[cfg(test)]
 mod test {
     use rstest::rstest;
 
-    use crate::test_utils::{prover_test_case, ProverTestCase};
+    use crate::test_utils::{prover_test_case, read_proof_file, ProverTestCase};
 
     use super::*;
 
@@ -217,4 +316,20 @@ mod test {
         assert!(annotation_file.exists());
         assert!(extra_output_file.exists());
     }
+
+    #[rstest]
+    fn test_verify_proof(prover_test_case: ProverTestCase) {
+        let proof = read_proof_file(&prover_test_case.proof_file);
+        verify_proof(&proof).expect("Proof is valid");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_verify_proof_with_annotations_async(prover_test_case: ProverTestCase) {
+        let proof = read_proof_file(&prover_test_case.proof_file);
+        let annotations = verify_proof_with_annotations_async(&proof)
+            .await
+            .expect("Proof is valid");
+        assert!(!annotations.annotations.is_empty());
+    }
 }